@@ -0,0 +1,86 @@
+//! Embeds the menu in another ratatui app: a sidebar to pick a planet from,
+//! next to a panel about the planet.
+//!
+//! Run it with `cargo run --example embed`.
+
+use std::io;
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::{
+    DefaultTerminal, Frame,
+    layout::{Constraint, Layout},
+    text::Line,
+    widgets::{Block, Paragraph},
+};
+use testo::{
+    menu::{Menu, MenuItem},
+    theme::Theme,
+    widget::{MenuState, MenuWidget},
+};
+
+const PLANETS: [(&str, &str); 4] = [
+    (
+        "Mercury",
+        "The smallest planet, and the closest to the Sun.",
+    ),
+    ("Venus", "The hottest planet, under thick clouds."),
+    ("Earth", "The only planet known to have life."),
+    ("Mars", "A cold desert with the tallest volcano."),
+];
+
+fn main() -> io::Result<()> {
+    let items = PLANETS
+        .iter()
+        .map(|&(name, about)| MenuItem {
+            description: Some(about.into()),
+            ..MenuItem::new(name)
+        })
+        .collect();
+    let mut menu = MenuState::new(Menu {
+        title: "Planets".into(),
+        items,
+    });
+    let terminal = ratatui::init();
+    let result = run(terminal, &mut menu);
+    ratatui::restore();
+    result
+}
+
+fn run(mut terminal: DefaultTerminal, menu: &mut MenuState) -> io::Result<()> {
+    loop {
+        terminal.draw(|frame| draw(frame, menu))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        // The menu only keeps track of the state; the app decides which keys
+        // do what.
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Up => menu.up(),
+            KeyCode::Down => menu.down(),
+            _ => {}
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, menu: &mut MenuState) {
+    let [sidebar, main] =
+        Layout::horizontal([Constraint::Length(20), Constraint::Fill(1)]).areas(frame.area());
+    let about = menu
+        .selected_item()
+        .and_then(|item| item.description.clone())
+        .unwrap_or_default();
+
+    let widget = MenuWidget::default()
+        .theme(Theme::preset("dark").unwrap_or_default())
+        .block(Block::bordered().title(Line::from(" Planets ").centered()))
+        .footer(Line::from("q to quit").centered());
+    frame.render_stateful_widget(widget, sidebar, menu);
+    frame.render_widget(
+        Paragraph::new(about).block(Block::bordered().title(" About ")),
+        main,
+    );
+}
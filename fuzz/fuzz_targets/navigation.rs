@@ -0,0 +1,7 @@
+//! Run with `cargo +nightly fuzz run navigation`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| testo::fuzz::check(data));
@@ -0,0 +1,1961 @@
+//! The app: the menu, the counter screens, background jobs and the event
+//! loop that drives them.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, io, mem,
+    rc::Rc,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use ratatui::{
+    Frame, Terminal,
+    backend::Backend,
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    text::{Line, Span, Text},
+    widgets::{Block, Paragraph, StatefulWidget, Widget},
+};
+
+use crate::{
+    counter::Counters,
+    events::{self, AppEvent, Events, Message, MessageSender},
+    history::{Change, History},
+    job::{Job, JobState},
+    keymap::{Context, KeyAction, Keymap},
+    menu::{Action, Menu, MenuItem, Toggle},
+    script::Recorder,
+    terminal,
+    theme::{self, Theme},
+    widget::{MenuState, MenuWidget},
+};
+
+/// The frames of the spinner shown while a job runs, one per tick.
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// How many lines of output the job pane shows, including its border.
+const JOB_PANE_HEIGHT: u16 = 8;
+
+/// The fewest rows the menu keeps its border in. Smaller areas, like short
+/// inline viewports, only fit the items.
+const COMPACT_HEIGHT: u16 = 4;
+
+/// How soon a second click on the same item counts as a double-click.
+const DOUBLE_CLICK: Duration = Duration::from_millis(500);
+
+/// The item chosen by a `print` action, or one of the items that were
+/// checked when a checkbox or radio button with one was chosen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selection {
+    /// The position of the item in its menu.
+    pub index: usize,
+    pub id: String,
+    pub label: String,
+    /// The value of the `print` action, or the label of a checked item that
+    /// has none.
+    pub value: String,
+}
+
+impl Selection {
+    fn new(index: usize, item: &MenuItem) -> Self {
+        let value = match &item.action {
+            Some(Action::Print(value)) => value.clone(),
+            _ => item.label.clone(),
+        };
+        Self {
+            index,
+            id: item.id.clone(),
+            label: item.label.clone(),
+            value,
+        }
+    }
+
+    /// Parses the value, like `selection.parse::<u16>()` for a port number.
+    pub fn parse<T: FromStr>(&self) -> Result<T, T::Err> {
+        self.value.parse()
+    }
+}
+
+/// A Rust function that menu items can call with `callback = "name"`. The
+/// returned message is shown in the status line.
+pub type Callback = Rc<dyn Fn(&mut App) -> String>;
+
+#[derive(Default, Clone)]
+struct Callbacks(HashMap<String, Callback>);
+
+impl fmt::Debug for Callbacks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Screen {
+    #[default]
+    Menu,
+    Counters,
+    Counter,
+    /// The output of the last background job.
+    Output,
+}
+
+impl Screen {
+    fn context(self) -> Context {
+        match self {
+            Screen::Menu => Context::Menu,
+            Screen::Counters => Context::Counters,
+            Screen::Counter => Context::Counter,
+            Screen::Output => Context::Output,
+        }
+    }
+}
+
+/// The name being typed for a new or renamed counter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct NameInput {
+    /// The counter being renamed, or `None` when creating one.
+    renaming: Option<usize>,
+    text: String,
+}
+
+/// Something to do with the whole terminal, outside of the app.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Outside {
+    /// Stop until the shell resumes the app.
+    Suspend,
+    /// Run a shell command that needs the terminal.
+    Run(String),
+}
+
+#[derive(Debug)]
+pub struct App {
+    exit: bool,
+    screen: Screen,
+    pub menu: MenuState,
+    /// The item clicked last and when, to detect double-clicks.
+    last_click: Option<(usize, Instant)>,
+    /// Where the app was drawn last.
+    area: Rect,
+    settings: BTreeMap<String, String>,
+    /// The theme from before the `theme` setting replaced it, to go back to
+    /// when the setting is undone.
+    unset_theme: Option<Theme>,
+    callbacks: Callbacks,
+    pub counters: Counters,
+    name_input: Option<NameInput>,
+    pub history: History,
+    show_history: bool,
+    pub keymap: Keymap,
+    pub theme: Theme,
+    pub events: Events,
+    /// Every command started from the menu, oldest first.
+    jobs: Vec<Job>,
+    /// The current frame of the spinner.
+    spinner: usize,
+    /// The job shown on the output screen, or `None` for the last one.
+    output_job: Option<usize>,
+    /// The first line of job output shown, or `None` to follow the end.
+    output_scroll: Option<usize>,
+    /// The result of the last action, shown below the menu.
+    status: Option<String>,
+    /// Printed to stdout once the terminal has been restored.
+    selections: Vec<Selection>,
+    /// Whether Esc quits, as the user is expected to choose or cancel.
+    picker: bool,
+    /// The signal that made the app quit, if any.
+    signal: Option<i32>,
+    /// What to do outside of the app once the current event is handled.
+    outside: Option<Outside>,
+    recorder: Option<Recorder>,
+    /// Events to replay instead of reading the terminal, and how fast.
+    replay: Option<(Vec<(Duration, AppEvent)>, u32)>,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new(Menu::default())
+    }
+}
+
+impl App {
+    pub fn new(menu: Menu) -> Self {
+        Self {
+            exit: Default::default(),
+            screen: Screen::default(),
+            menu: MenuState::new(menu),
+            last_click: None,
+            area: Rect::default(),
+            settings: BTreeMap::new(),
+            unset_theme: None,
+            callbacks: Callbacks::default(),
+            counters: Counters::default(),
+            name_input: None,
+            history: History::default(),
+            show_history: false,
+            keymap: Keymap::default(),
+            theme: Theme::default(),
+            events: Events::default(),
+            jobs: Vec::new(),
+            spinner: 0,
+            output_job: None,
+            output_scroll: None,
+            status: None,
+            selections: Vec::new(),
+            picker: false,
+            signal: None,
+            outside: None,
+            recorder: None,
+            replay: None,
+        }
+    }
+
+    /// A flat menu where choosing an item prints its label.
+    pub fn picker(items: Vec<String>) -> Self {
+        Self::picker_of(items, None)
+    }
+
+    /// A flat menu of checkboxes where choosing an item prints the labels
+    /// of the checked ones, or its own if none are.
+    pub fn multi_picker(items: Vec<String>) -> Self {
+        Self::picker_of(items, Some(Toggle::Checkbox))
+    }
+
+    fn picker_of(items: Vec<String>, toggle: Option<Toggle>) -> Self {
+        let items = items
+            .into_iter()
+            .enumerate()
+            .map(|(index, label)| MenuItem {
+                id: index.to_string(),
+                toggle: toggle.clone(),
+                action: Some(Action::Print(label.clone())),
+                ..MenuItem::new(label)
+            })
+            .collect();
+        Self {
+            picker: true,
+            ..Self::new(Menu {
+                title: "Select".into(),
+                items,
+            })
+        }
+    }
+
+    pub fn register_callback(
+        &mut self,
+        name: impl Into<String>,
+        callback: impl Fn(&mut App) -> String + 'static,
+    ) {
+        self.callbacks.0.insert(name.into(), Rc::new(callback));
+    }
+
+    /// The item chosen by a `print` action, once the app has quit, or the
+    /// first of the checked items.
+    pub fn selection(&self) -> Option<&Selection> {
+        self.selections.first()
+    }
+
+    /// The items that were checked when a checkbox or radio button with a
+    /// `print` action was chosen, or else the item chosen, once the app has
+    /// quit.
+    pub fn selections(&self) -> &[Selection] {
+        &self.selections
+    }
+
+    /// Whether the app has quit, or is about to once the current event is
+    /// handled.
+    pub fn has_quit(&self) -> bool {
+        self.exit
+    }
+
+    /// The message in the status line.
+    pub fn status(&self) -> Option<&str> {
+        self.status.as_deref()
+    }
+
+    /// The signal that made the app quit, if any.
+    pub fn signal(&self) -> Option<i32> {
+        self.signal
+    }
+
+    /// A line about what was chosen, like `Select › grape` or
+    /// `Select › grape, lime`.
+    pub fn summary(&self) -> Option<String> {
+        if self.selections.is_empty() {
+            return None;
+        }
+        let labels: Vec<_> = self.selections.iter().map(|s| s.label.as_str()).collect();
+        Some(format!("{} › {}", self.menu.title, labels.join(", ")))
+    }
+
+    /// Sends messages to the app from background tasks.
+    pub fn sender(&self) -> MessageSender {
+        self.events.sender()
+    }
+
+    /// Runs `command` with the whole terminal, e.g. an editor, once the
+    /// current event is handled. The app comes back when the command exits.
+    pub fn run_interactive(&mut self, command: impl Into<String>) {
+        self.outside = Some(Outside::Run(command.into()));
+    }
+
+    /// Records every event the app receives while it runs.
+    pub fn record(&mut self, recorder: Recorder) {
+        self.recorder = Some(recorder);
+    }
+
+    /// Makes the app run on `events` instead of the terminal, until they
+    /// are all sent. See [`Events::replay`].
+    pub fn replay(&mut self, events: Vec<(Duration, AppEvent)>, speed: u32) {
+        self.replay = Some((events, speed));
+    }
+
+    pub fn run<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> io::Result<()> {
+        let mut input = match self.replay.take() {
+            Some((events, speed)) => self.events.replay(events, speed),
+            None => self.events.read_terminal(),
+        };
+        let _signals = self.events.watch_signals()?;
+        // Replays start from the same size, so that layouts and mouse
+        // positions match the recording.
+        if let Some(recorder) = &mut self.recorder {
+            let size = terminal.size()?;
+            recorder.record(&AppEvent::Terminal(Event::Resize(size.width, size.height)))?;
+        }
+        let mut dirty = true;
+        while !self.exit {
+            if dirty {
+                terminal.draw(|frame| self.draw(frame))?;
+            }
+            let event = self.events.recv()?;
+            if let Some(recorder) = &mut self.recorder {
+                recorder.record(&event)?;
+            }
+            dirty = self.handle_event(event);
+            // A replay cannot type into a shell or a command, so it stays.
+            if let Some(outside) = self.outside.take()
+                && !self.events.is_replaying()
+            {
+                // Keys are for the shell or the command until we are back.
+                drop(input);
+                terminal::suspend()?;
+                self.go_outside(outside);
+                terminal::resume()?;
+                terminal.clear()?;
+                input = self.events.read_terminal();
+                dirty = true;
+            }
+        }
+        for job in &mut self.jobs {
+            job.cancel();
+        }
+        for job in &mut self.jobs {
+            job.reap();
+        }
+        Ok(())
+    }
+
+    pub fn draw(&mut self, frame: &mut Frame) {
+        frame.render_widget(self, frame.area());
+    }
+
+    /// Updates the app for `event`. Returns whether anything changed that
+    /// needs to be redrawn.
+    pub(crate) fn handle_event(&mut self, event: AppEvent) -> bool {
+        match event {
+            // it's important to check that the event is a key press event as
+            // crossterm also emits key release and repeat events on Windows.
+            AppEvent::Terminal(Event::Key(key_event)) if key_event.kind == KeyEventKind::Press => {
+                self.handle_key_event(key_event);
+                true
+            }
+            AppEvent::Terminal(Event::Mouse(mouse_event)) => {
+                // The pointer moves a lot, usually without changing anything.
+                let hovered = self.menu.hovered();
+                self.handle_mouse_event(mouse_event);
+                mouse_event.kind != MouseEventKind::Moved || self.menu.hovered() != hovered
+            }
+            AppEvent::Terminal(Event::Paste(text)) => {
+                self.handle_paste(&text);
+                true
+            }
+            AppEvent::Terminal(Event::Resize(..)) => true,
+            AppEvent::Terminal(_) => false,
+            AppEvent::Tick => self.tick(),
+            AppEvent::Signal(libc::SIGTSTP) => {
+                self.outside = Some(Outside::Suspend);
+                false
+            }
+            AppEvent::Signal(signal) => {
+                self.signal = Some(signal);
+                self.exit();
+                false
+            }
+            AppEvent::Message(message) => {
+                match message {
+                    Message::Status(status) => self.status = Some(status),
+                    Message::JobOutput { job, line } => {
+                        if let Some(job) = self.jobs.get_mut(job) {
+                            job.output.push(line);
+                        }
+                    }
+                    Message::JobClosed { job } => {
+                        if let Some(job) = self.jobs.get_mut(job) {
+                            job.close_stream();
+                        }
+                        self.poll_jobs();
+                    }
+                }
+                true
+            }
+        }
+    }
+
+    /// Does `outside` while the terminal is suspended.
+    fn go_outside(&mut self, outside: Outside) {
+        match outside {
+            Outside::Suspend => {
+                // Stops the process until it gets SIGCONT.
+                let _ = signal_hook::low_level::emulate_default_handler(libc::SIGTSTP);
+            }
+            Outside::Run(command) => {
+                let status = terminal::shell_command(&command)
+                    .and_then(|mut shell| shell.spawn())
+                    .and_then(|mut child| {
+                        let handed_over = events::hand_over_signals();
+                        let status = child.wait();
+                        handed_over.and(status)
+                    });
+                let state = match status {
+                    Ok(status) => status.code().map_or(JobState::Killed, JobState::Exited),
+                    Err(error) => JobState::Failed(error.to_string()),
+                };
+                self.status = Some(format!("`{command}` {state}"));
+            }
+        }
+    }
+
+    /// Advances the spinner and checks on the jobs. Returns whether anything
+    /// changed.
+    fn tick(&mut self) -> bool {
+        let finished = self.poll_jobs();
+        let running = self.jobs.iter().any(Job::is_running);
+        if running {
+            self.spinner = (self.spinner + 1) % SPINNER.len();
+        }
+        finished || running
+    }
+
+    /// Reports the jobs that are done in the status line. Returns whether
+    /// any are.
+    fn poll_jobs(&mut self) -> bool {
+        let mut finished = false;
+        for job in &mut self.jobs {
+            if job.poll() {
+                self.status = Some(job.summary());
+                finished = true;
+            }
+        }
+        finished
+    }
+
+    fn handle_mouse_event(&mut self, mouse_event: MouseEvent) {
+        if self.screen != Screen::Menu || self.name_input.is_some() {
+            return;
+        }
+        let position = self.menu.position_at(mouse_event.column, mouse_event.row);
+        match mouse_event.kind {
+            MouseEventKind::Moved => self.menu.hover(position),
+            MouseEventKind::ScrollUp => self.move_in_menu(MenuState::up),
+            MouseEventKind::ScrollDown => self.move_in_menu(MenuState::down),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let Some(position) = position.filter(|&p| self.menu.is_selectable(p)) else {
+                    self.last_click = None;
+                    return;
+                };
+                let item = self.menu.view_item(position);
+                let now = Instant::now();
+                let double_click = self.last_click.is_some_and(|(last_item, at)| {
+                    last_item == item && now.duration_since(at) <= DOUBLE_CLICK
+                });
+                self.move_in_menu(|menu| menu.select_position(position));
+                if double_click {
+                    self.last_click = None;
+                    self.activate();
+                } else {
+                    self.last_click = Some((item, now));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent) {
+        if self.name_input.is_some() {
+            return self.handle_name_input_key_event(key_event);
+        }
+        // The filter stays open while a counter chosen in it is shown, but
+        // only takes keys on the menu.
+        if self.screen == Screen::Menu
+            && self.menu.filter().is_some()
+            && !self.is_picker_shortcut(key_event)
+            && self.handle_filter_key_event(key_event)
+        {
+            return;
+        }
+        // Pickers are for finding an item, so characters always type, and
+        // only the other keys are bindings.
+        if self.picker
+            && self.screen == Screen::Menu
+            && is_typing(key_event)
+            && !self.is_picker_shortcut(key_event)
+        {
+            self.menu.start_filter();
+            self.handle_filter_key_event(key_event);
+            return;
+        }
+        let action = self.keymap.action(self.screen.context(), key_event);
+        if let Some(job) = self.jobs.iter_mut().rev().find(|job| job.is_running())
+            && (key_event.code == KeyCode::Esc || action == Some(KeyAction::Cancel))
+        {
+            return job.cancel();
+        }
+        if self.picker && key_event.code == KeyCode::Esc {
+            return self.exit();
+        }
+        let Some(action) = action else {
+            // Typing on the menu starts filtering it.
+            if self.screen == Screen::Menu && is_typing(key_event) {
+                self.menu.start_filter();
+                self.handle_filter_key_event(key_event);
+            }
+            return;
+        };
+        match action {
+            KeyAction::Quit => self.exit(),
+            KeyAction::Undo => self.undo(),
+            KeyAction::Redo => self.redo(),
+            KeyAction::History => self.show_history = !self.show_history,
+            KeyAction::Cancel => {}
+            KeyAction::Suspend => self.outside = Some(Outside::Suspend),
+            KeyAction::Output => {
+                if self.jobs.is_empty() {
+                    self.status = Some("no command has run yet".into());
+                } else {
+                    self.screen = Screen::Output;
+                    self.output_job = None;
+                    self.output_scroll = None;
+                }
+            }
+            action => match self.screen {
+                Screen::Menu => self.handle_menu_action(action),
+                Screen::Counters => self.handle_counters_action(action),
+                Screen::Counter => self.handle_counter_action(action),
+                Screen::Output => self.handle_output_action(action),
+            },
+        }
+    }
+
+    /// Whether a character typed in a picker is a binding rather than part
+    /// of the query: until something is typed, the keys to quit and to check
+    /// items keep working.
+    fn is_picker_shortcut(&self, key_event: KeyEvent) -> bool {
+        if !self.picker
+            || !is_typing(key_event)
+            || self
+                .menu
+                .filter()
+                .is_some_and(|filter| !filter.query().is_empty())
+        {
+            return false;
+        }
+        match self.keymap.action(self.screen.context(), key_event) {
+            Some(KeyAction::Quit) => true,
+            Some(KeyAction::Toggle) => self
+                .menu
+                .selected_item()
+                .is_some_and(|item| item.toggle.is_some()),
+            _ => false,
+        }
+    }
+
+    /// Edits the filter query. Returns `false` for keys that are left to the
+    /// key bindings, like the arrows to move through the matches.
+    fn handle_filter_key_event(&mut self, key_event: KeyEvent) -> bool {
+        match key_event.code {
+            KeyCode::Char(c) if is_typing(key_event) => self.menu.push_filter(c),
+            // Deleting past the start or Esc closes the filter and leaves the
+            // selection on the item that was selected in it.
+            KeyCode::Backspace => self.menu.pop_filter(),
+            KeyCode::Esc => self.menu.close_filter(),
+            _ => return false,
+        }
+        true
+    }
+
+    fn handle_output_action(&mut self, action: KeyAction) {
+        let Some((index, job)) = self.output_job() else {
+            return;
+        };
+        let page = self.output_height().max(1);
+        let last_top = job.output.len().saturating_sub(page);
+        let top = self.output_scroll.unwrap_or(last_top);
+        let top = match action {
+            KeyAction::Up => top.saturating_sub(1),
+            KeyAction::Down => top + 1,
+            KeyAction::PageUp => top.saturating_sub(page),
+            KeyAction::PageDown => top + page,
+            KeyAction::First => 0,
+            KeyAction::Last => last_top,
+            KeyAction::Previous | KeyAction::Next => {
+                let index = if action == KeyAction::Previous {
+                    index.saturating_sub(1)
+                } else {
+                    index + 1
+                };
+                // Going past the last job follows new jobs again.
+                self.output_job = (index + 1 < self.jobs.len()).then_some(index);
+                self.output_scroll = None;
+                return;
+            }
+            KeyAction::Back => {
+                self.screen = Screen::Menu;
+                return;
+            }
+            _ => return,
+        };
+        // Scrolling to the end follows new output again.
+        self.output_scroll = (top < last_top).then_some(top);
+    }
+
+    /// The job shown on the output screen and its index.
+    fn output_job(&self) -> Option<(usize, &Job)> {
+        let index = self.output_job.unwrap_or(self.jobs.len().checked_sub(1)?);
+        Some((index, self.jobs.get(index)?))
+    }
+
+    /// How many lines of output fit on the output screen.
+    fn output_height(&self) -> usize {
+        let (main_area, _) = self.split_history(self.area);
+        Block::bordered().inner(main_area).height.into()
+    }
+
+    fn handle_counter_action(&mut self, action: KeyAction) {
+        match action {
+            KeyAction::Decrement => self.change_counter(Counters::decrement),
+            KeyAction::Increment => self.change_counter(Counters::increment),
+            KeyAction::Reset => self.change_counter(Counters::reset),
+            KeyAction::Back => self.screen = Screen::Counters,
+            _ => {}
+        }
+    }
+
+    /// Applies `change` to the active counter and records it for undo.
+    fn change_counter(&mut self, change: fn(&mut Counters)) {
+        let index = self.counters.active_index();
+        let from = self.counters.active().value;
+        change(&mut self.counters);
+        let to = self.counters.active().value;
+        if from != to {
+            self.history
+                .record(Change::CounterValue { index, from, to });
+        }
+    }
+
+    fn handle_counters_action(&mut self, action: KeyAction) {
+        let active = self.counters.active_index();
+        match action {
+            KeyAction::Up => {
+                let len = self.counters.len();
+                self.counters.select((active + len - 1) % len);
+            }
+            KeyAction::Down => {
+                self.counters.select((active + 1) % self.counters.len());
+            }
+            KeyAction::Open => self.screen = Screen::Counter,
+            KeyAction::New => self.name_input = Some(NameInput::default()),
+            KeyAction::Rename => {
+                self.name_input = Some(NameInput {
+                    renaming: Some(active),
+                    text: self.counters.active().name.clone(),
+                });
+            }
+            KeyAction::Delete => {
+                self.status = Some(match self.counters.delete(active) {
+                    Ok(counter) => {
+                        let status = format!("deleted `{}`", counter.name);
+                        self.history.record(Change::CounterDelete {
+                            index: active,
+                            counter,
+                        });
+                        status
+                    }
+                    Err(error) => error,
+                });
+            }
+            KeyAction::Back => self.screen = Screen::Menu,
+            _ => {}
+        }
+    }
+
+    /// Types the first line of `text` into the counter name or the menu
+    /// filter.
+    fn handle_paste(&mut self, text: &str) {
+        let line = text.lines().next().unwrap_or_default();
+        let chars = line.chars().filter(|c| !c.is_control());
+        if let Some(input) = &mut self.name_input {
+            input.text.extend(chars);
+        } else if self.screen == Screen::Menu {
+            if self.menu.filter().is_none() {
+                self.menu.start_filter();
+            }
+            chars.for_each(|c| self.menu.push_filter(c));
+        }
+    }
+
+    fn handle_name_input_key_event(&mut self, key_event: KeyEvent) {
+        let Some(input) = &mut self.name_input else {
+            return;
+        };
+        match key_event.code {
+            KeyCode::Char(c) => input.text.push(c),
+            KeyCode::Backspace => {
+                input.text.pop();
+            }
+            KeyCode::Esc => self.name_input = None,
+            KeyCode::Enter => {
+                let result = match input.renaming {
+                    Some(index) => {
+                        let from = self.counters.active().name.clone();
+                        self.counters.rename(index, &input.text).map(|()| {
+                            let to = self.counters.active().name.clone();
+                            Change::CounterRename { index, from, to }
+                        })
+                    }
+                    None => self
+                        .counters
+                        .create(&input.text)
+                        .map(|()| Change::CounterCreate {
+                            index: self.counters.active_index(),
+                            counter: self.counters.active().clone(),
+                        }),
+                };
+                match result {
+                    Ok(change) => {
+                        self.history.record(change);
+                        self.name_input = None;
+                        self.status = None;
+                    }
+                    Err(error) => self.status = Some(error),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_menu_action(&mut self, action: KeyAction) {
+        match action {
+            KeyAction::Up => self.move_in_menu(MenuState::up),
+            KeyAction::Down => self.move_in_menu(MenuState::down),
+            KeyAction::PageUp => self.move_in_menu(|menu| {
+                menu.select_position(menu.view_position().saturating_sub(menu.page_size()));
+            }),
+            KeyAction::PageDown => self.move_in_menu(|menu| {
+                menu.select_position(menu.view_position().saturating_add(menu.page_size()));
+            }),
+            KeyAction::First => self.move_in_menu(|menu| menu.select_position(0)),
+            KeyAction::Last => self.move_in_menu(|menu| menu.select_position(usize::MAX)),
+            KeyAction::Activate => self.activate(),
+            KeyAction::Toggle => self.toggle(),
+            KeyAction::Open => {
+                self.menu.open();
+            }
+            KeyAction::Back => {
+                self.menu.close();
+            }
+            KeyAction::Filter => self.menu.start_filter(),
+            _ => {}
+        }
+    }
+
+    /// Moves the selection in the menu and records the move for undo.
+    fn move_in_menu(&mut self, move_selection: impl FnOnce(&mut MenuState)) {
+        let from = self.menu.selected();
+        move_selection(&mut self.menu);
+        let to = self.menu.selected();
+        if from != to {
+            self.history.record(Change::MenuMove {
+                stack: self.menu.path().to_vec(),
+                from,
+                to,
+            });
+        }
+    }
+
+    /// Toggles the selected checkbox or radio button and records it for
+    /// undo.
+    fn toggle(&mut self) {
+        let from = self.menu.checked();
+        if !self.menu.toggle() {
+            return;
+        }
+        let to = self.menu.checked();
+        if from != to {
+            let index = self.menu.selected();
+            self.history.record(Change::Toggle {
+                stack: self.menu.path().to_vec(),
+                index,
+                label: self.menu.items()[index].label.clone(),
+                from,
+                to,
+            });
+        }
+    }
+
+    /// Runs the action of the active item, opens its submenu, or toggles
+    /// it if it is a checkbox or radio button without an action.
+    fn activate(&mut self) {
+        let Some(item) = self.menu.selected_item() else {
+            return;
+        };
+        if !item.children.is_empty() {
+            self.menu.open();
+            return;
+        }
+        let Some(action) = item.action.clone() else {
+            if item.toggle.is_some() {
+                self.toggle();
+            }
+            return;
+        };
+        let status = match action {
+            Action::Command(command) => {
+                let job = Job::spawn(self.jobs.len(), &command, &self.sender());
+                let status = (!job.is_running()).then(|| job.summary());
+                self.jobs.push(job);
+                match status {
+                    Some(status) => status,
+                    None => return,
+                }
+            }
+            Action::Interactive(command) => {
+                self.run_interactive(command);
+                return;
+            }
+            Action::Print(_) => {
+                // Choosing a checkbox or radio button chooses all the checked
+                // ones; other items only stand for themselves.
+                let checked = match item.toggle {
+                    Some(_) => self.menu.checked_items(),
+                    None => Vec::new(),
+                };
+                self.selections = if checked.is_empty() {
+                    vec![Selection::new(self.menu.selected(), item)]
+                } else {
+                    checked
+                        .into_iter()
+                        .map(|(index, item)| Selection::new(index, item))
+                        .collect()
+                };
+                self.exit();
+                return;
+            }
+            Action::Set { key, value } => {
+                let status = format!("{key} = {value}");
+                let from = self.settings.insert(key.clone(), value.clone());
+                self.apply_setting(&key);
+                self.history.record(Change::Setting {
+                    key,
+                    from,
+                    to: value,
+                });
+                status
+            }
+            Action::Callback(name) => match self.callbacks.0.get(&name).cloned() {
+                Some(callback) => callback(self),
+                None => format!("no callback named `{name}` is registered"),
+            },
+            Action::Counter(config) => {
+                self.counters.configure(config);
+                self.screen = Screen::Counters;
+                return;
+            }
+        };
+        self.status = Some(status);
+    }
+
+    /// Makes the setting `key` take effect. Only `theme` does anything, the
+    /// other settings are left for callbacks to read.
+    fn apply_setting(&mut self, key: &str) {
+        if key != "theme" {
+            return;
+        }
+        let preset = self.settings.get(key).and_then(|name| Theme::preset(name));
+        match preset {
+            Some(theme) => {
+                let previous = mem::replace(
+                    &mut self.theme,
+                    if theme::no_color() {
+                        theme.without_colors()
+                    } else {
+                        theme
+                    },
+                );
+                self.unset_theme.get_or_insert(previous);
+            }
+            None => {
+                if let Some(theme) = self.unset_theme.take() {
+                    self.theme = theme;
+                }
+            }
+        }
+    }
+
+    fn undo(&mut self) {
+        self.status = Some(match self.history.undo().cloned() {
+            Some(change) => {
+                self.apply(&change, true);
+                format!("undid {change}")
+            }
+            None => "nothing to undo".into(),
+        });
+    }
+
+    fn redo(&mut self) {
+        self.status = Some(match self.history.redo().cloned() {
+            Some(change) => {
+                self.apply(&change, false);
+                format!("redid {change}")
+            }
+            None => "nothing to redo".into(),
+        });
+    }
+
+    /// Applies `change`, or reverts it if `revert` is set, and shows the
+    /// screen where it happened.
+    fn apply(&mut self, change: &Change, revert: bool) {
+        fn pick<T>(revert: bool, from: T, to: T) -> T {
+            if revert { from } else { to }
+        }
+        match change {
+            Change::MenuMove { stack, from, to } => {
+                self.screen = Screen::Menu;
+                self.menu.show(stack, pick(revert, *from, *to));
+            }
+            Change::CounterValue { index, from, to } => {
+                self.counters.set_value(*index, pick(revert, *from, *to));
+                self.counters.select(*index);
+            }
+            Change::CounterRename { index, from, to } => {
+                // Names are unique at every point in the history, so this
+                // cannot clash.
+                let _ = self.counters.rename(*index, pick(revert, from, to));
+                self.counters.select(*index);
+            }
+            Change::CounterCreate { index, .. } if revert => {
+                let _ = self.counters.delete(*index);
+            }
+            Change::CounterDelete { index, .. } if !revert => {
+                let _ = self.counters.delete(*index);
+            }
+            Change::CounterCreate { index, counter } | Change::CounterDelete { index, counter } => {
+                self.counters.insert(*index, counter.clone());
+            }
+            Change::Setting { key, from, to } => match pick(revert, from.as_ref(), Some(to)) {
+                Some(value) => {
+                    self.settings.insert(key.clone(), value.clone());
+                    self.apply_setting(key);
+                }
+                None => {
+                    self.settings.remove(key);
+                    self.apply_setting(key);
+                }
+            },
+            Change::Toggle {
+                stack,
+                index,
+                from,
+                to,
+                ..
+            } => {
+                self.screen = Screen::Menu;
+                self.menu.show(stack, *index);
+                self.menu.set_checked(pick(revert, from, to));
+            }
+        }
+        if matches!(self.screen, Screen::Menu | Screen::Output)
+            && !matches!(
+                change,
+                Change::MenuMove { .. } | Change::Setting { .. } | Change::Toggle { .. }
+            )
+        {
+            self.screen = Screen::Counters;
+        }
+    }
+
+    fn exit(&mut self) {
+        self.exit = true;
+    }
+}
+
+/// Whether `key_event` types a character rather than being a shortcut.
+fn is_typing(key_event: KeyEvent) -> bool {
+    matches!(key_event.code, KeyCode::Char(_))
+        && !key_event
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+}
+
+impl Widget for &mut App {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        self.area = area;
+        let (area, history_area) = self.split_history(area);
+        if let Some(history_area) = history_area {
+            self.render_history(history_area, buf);
+        }
+        let (area, job_area) = self.split_job_pane(area);
+        if let (Some(job_area), Some(job)) = (job_area, self.jobs.last()) {
+            self.render_job_pane(job, job_area, buf);
+        }
+        match self.screen {
+            Screen::Menu => self.render_menu(area, buf),
+            Screen::Counters => self.render_counters(area, buf),
+            Screen::Counter => self.render_counter(area, buf),
+            Screen::Output => self.render_output(area, buf),
+        }
+    }
+}
+
+impl App {
+    /// Splits off the history panel on the right, if it is shown.
+    fn split_history(&self, area: Rect) -> (Rect, Option<Rect>) {
+        if !self.show_history {
+            return (area, None);
+        }
+        let [main_area, history_area] =
+            Layout::horizontal([Constraint::Fill(1), Constraint::Length(32)]).areas(area);
+        (main_area, Some(history_area))
+    }
+
+    /// Splits off the pane at the bottom that follows the output of the last
+    /// job while it runs.
+    fn split_job_pane(&self, area: Rect) -> (Rect, Option<Rect>) {
+        let running = self.jobs.last().is_some_and(Job::is_running);
+        let fits = area.height >= JOB_PANE_HEIGHT + COMPACT_HEIGHT;
+        if !running || !fits || self.screen == Screen::Output {
+            return (area, None);
+        }
+        let [main_area, job_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(JOB_PANE_HEIGHT)])
+                .areas(area);
+        (main_area, Some(job_area))
+    }
+
+    /// The border around the menu, which is left out when `area` is too
+    /// small to spare the rows.
+    fn menu_block(&self, area: Rect) -> Block<'static> {
+        if area.height < COMPACT_HEIGHT {
+            return Block::new();
+        }
+        let title = Line::styled(
+            format!(" {} ", self.menu.breadcrumbs().join(" › ")),
+            self.theme.title,
+        );
+        // Characters type in pickers, so they are left with Esc.
+        let instructions = if self.picker {
+            Line::from(vec![
+                " Cancel ".into(),
+                Span::styled("<Esc> ", self.theme.key_hint),
+            ])
+        } else {
+            self.instructions(Context::Menu, &[KeyAction::Quit])
+        };
+        Block::bordered()
+            .title(title.centered())
+            .title_bottom(instructions.centered())
+            .border_set(self.theme.border)
+    }
+
+    /// Key hints like ` Quit <q> ` for the actions that have a key bound on
+    /// the screen `context`.
+    fn instructions(&self, context: Context, actions: &[KeyAction]) -> Line<'static> {
+        let mut spans = Vec::new();
+        for &action in actions {
+            if let Some(key) = self.keymap.key(context, action) {
+                spans.push(format!(" {} ", action.label()).into());
+                spans.push(Span::styled(format!("<{key}>"), self.theme.key_hint));
+            }
+        }
+        if let Some(last) = spans.last_mut() {
+            last.content.to_mut().push(' ');
+        }
+        Line::from(spans)
+    }
+
+    fn render_history(&self, area: Rect, buf: &mut Buffer) {
+        let block = Block::bordered()
+            .title(Line::styled(" History ", self.theme.title).centered())
+            .title_bottom(
+                self.instructions(self.screen.context(), &[KeyAction::Undo, KeyAction::Redo]),
+            )
+            .border_set(self.theme.border);
+        // Undone changes are greyed out above the ones that can be undone, so
+        // the most recent change is always on the line between them.
+        let undone: Vec<_> = self.history.undone().collect();
+        let lines: Vec<Line> = undone
+            .into_iter()
+            .rev()
+            .map(|change| Line::styled(change.to_string(), self.theme.disabled))
+            .chain(
+                self.history
+                    .done()
+                    .map(|change| Line::from(change.to_string())),
+            )
+            .collect();
+        Paragraph::new(Text::from(lines))
+            .block(block)
+            .render(area, buf);
+    }
+
+    /// Like ` ⠹ make 3s ` while the job runs, or ` make exited with 2 `.
+    fn job_title(&self, job: &Job) -> Line<'static> {
+        let title = if job.is_running() {
+            format!(
+                " {} {} {}s ",
+                SPINNER[self.spinner],
+                job.command,
+                job.elapsed().as_secs()
+            )
+        } else {
+            format!(" {} {} ", job.command, job.state)
+        };
+        Line::styled(title, self.theme.title)
+    }
+
+    /// The last lines of `job`'s output that fit in `height` lines, or the
+    /// ones from `top` on.
+    fn output_lines<'a>(&self, job: &'a Job, height: usize, top: Option<usize>) -> Vec<Line<'a>> {
+        let last_top = job.output.len().saturating_sub(height);
+        let top = top.unwrap_or(last_top).min(last_top);
+        job.output[top..job.output.len().min(top + height)]
+            .iter()
+            .map(|line| Line::from(line.as_str()))
+            .collect()
+    }
+
+    fn render_job_pane(&self, job: &Job, area: Rect, buf: &mut Buffer) {
+        let block = Block::bordered()
+            .title(self.job_title(job))
+            .title_bottom(
+                self.instructions(Context::Global, &[KeyAction::Cancel, KeyAction::Output])
+                    .right_aligned(),
+            )
+            .border_set(self.theme.border);
+        let height = block.inner(area).height.into();
+        Paragraph::new(self.output_lines(job, height, None))
+            .block(block)
+            .render(area, buf);
+    }
+
+    fn render_output(&self, area: Rect, buf: &mut Buffer) {
+        let Some((index, job)) = self.output_job() else {
+            return;
+        };
+        let position = format!(" {}/{} ", index + 1, self.jobs.len());
+        let block = Block::bordered()
+            .title(Line::styled(position, self.theme.title).left_aligned())
+            .title(self.job_title(job).centered())
+            .title_bottom(
+                self.instructions(
+                    Context::Output,
+                    &[
+                        KeyAction::Up,
+                        KeyAction::Down,
+                        KeyAction::Previous,
+                        KeyAction::Next,
+                        KeyAction::Back,
+                    ],
+                )
+                .centered(),
+            )
+            .border_set(self.theme.border);
+        let height = block.inner(area).height.into();
+        Paragraph::new(self.output_lines(job, height, self.output_scroll))
+            .block(block)
+            .render(area, buf);
+    }
+
+    fn render_counters(&self, area: Rect, buf: &mut Buffer) {
+        let title = Line::styled(" Counters ", self.theme.title);
+        let instructions = self.instructions(
+            Context::Counters,
+            &[
+                KeyAction::Open,
+                KeyAction::New,
+                KeyAction::Rename,
+                KeyAction::Delete,
+                KeyAction::Back,
+            ],
+        );
+        let block = Block::bordered()
+            .title(title.centered())
+            .title_bottom(instructions.centered())
+            .border_set(self.theme.border);
+
+        let counter_lines: Vec<Line> = self
+            .counters
+            .iter()
+            .enumerate()
+            .map(|(i, counter)| {
+                let line = Line::from(vec![
+                    format!("{}: ", counter.name).into(),
+                    Span::styled(counter.value.to_string(), self.theme.value),
+                ]);
+                if self.counters.active_index() == i {
+                    line.patch_style(self.theme.selected)
+                } else {
+                    line.patch_style(self.theme.unselected)
+                }
+            })
+            .collect();
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+        let [list_area, input_area, status_area] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(self.name_input.is_some().into()),
+            Constraint::Length(self.status.is_some().into()),
+        ])
+        .areas(inner);
+
+        Paragraph::new(Text::from(counter_lines))
+            .centered()
+            .render(list_area, buf);
+        if let Some(input) = &self.name_input {
+            Line::from(vec![
+                Span::styled("Name: ", self.theme.prompt),
+                input.text.as_str().into(),
+                "█".into(),
+            ])
+            .centered()
+            .render(input_area, buf);
+        }
+        if let Some(status) = &self.status {
+            Line::styled(status.as_str(), self.theme.status)
+                .centered()
+                .render(status_area, buf);
+        }
+    }
+
+    fn render_counter(&self, area: Rect, buf: &mut Buffer) {
+        let title = Line::styled(" Counter App Tutorial ", self.theme.title);
+        let instructions = self.instructions(
+            Context::Counter,
+            &[KeyAction::Decrement, KeyAction::Increment, KeyAction::Quit],
+        );
+        let block = Block::bordered()
+            .title(title.centered())
+            .title_bottom(instructions.centered())
+            .border_set(self.theme.border);
+
+        let counter_text = Text::from(vec![Line::from(vec![
+            "Value: ".into(),
+            Span::styled(self.counters.active().value.to_string(), self.theme.value),
+        ])]);
+
+        Paragraph::new(counter_text)
+            .centered()
+            .block(block)
+            .render(area, buf);
+    }
+
+    fn render_menu(&mut self, area: Rect, buf: &mut Buffer) {
+        let mut menu = MenuWidget::default()
+            .block(self.menu_block(area))
+            .theme(self.theme);
+        if let Some(status) = &self.status {
+            menu = menu.footer(Line::styled(status.as_str(), self.theme.status).centered());
+        }
+        menu.render(area, buf, &mut self.menu);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{
+        backend::TestBackend,
+        style::{Color, Style, Stylize},
+    };
+
+    #[test]
+    fn render() {
+        let mut app = App {
+            screen: Screen::Counter,
+            ..App::default()
+        };
+        let mut buf = Buffer::empty(Rect::new(0, 0, 50, 4));
+
+        app.render(buf.area, &mut buf);
+
+        let mut expected = Buffer::with_lines(vec![
+            "┏━━━━━━━━━━━━━ Counter App Tutorial ━━━━━━━━━━━━━┓",
+            "┃                    Value: 0                    ┃",
+            "┃                                                ┃",
+            "┗━ Decrement <Left> Increment <Right> Quit <q> ━━┛",
+        ]);
+        let title_style = Style::new().bold();
+        let counter_style = Style::new().yellow();
+        let key_style = Style::new().blue().bold();
+        expected.set_style(Rect::new(14, 0, 22, 1), title_style);
+        expected.set_style(Rect::new(28, 1, 1, 1), counter_style);
+        expected.set_style(Rect::new(13, 3, 6, 1), key_style);
+        expected.set_style(Rect::new(30, 3, 7, 1), key_style);
+        expected.set_style(Rect::new(43, 3, 4, 1), key_style);
+
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn handle_key_event() -> io::Result<()> {
+        let mut app = App::default();
+
+        app.handle_key_event(KeyCode::Down.into());
+        assert_eq!(app.menu.selected(), 1);
+
+        app.handle_key_event(KeyCode::Up.into());
+        assert_eq!(app.menu.selected(), 0);
+
+        let mut app = App::default();
+        app.handle_key_event(KeyCode::Char('q').into());
+        assert!(app.exit);
+
+        Ok(())
+    }
+
+    #[test]
+    fn counter_screen() {
+        let mut app = App::default();
+        app.handle_key_event(KeyCode::Up.into());
+        app.handle_key_event(KeyCode::Enter.into());
+        assert_eq!(app.screen, Screen::Counters);
+        app.handle_key_event(KeyCode::Enter.into());
+        assert_eq!(app.screen, Screen::Counter);
+
+        app.handle_key_event(KeyCode::Right.into());
+        app.handle_key_event(KeyCode::Right.into());
+        app.handle_key_event(KeyCode::Left.into());
+        assert_eq!(app.counters.active().value, 1);
+        app.handle_key_event(KeyCode::Char('r').into());
+        assert_eq!(app.counters.active().value, 0);
+
+        app.handle_key_event(KeyCode::Esc.into());
+        assert_eq!(app.screen, Screen::Counters);
+        app.handle_key_event(KeyCode::Esc.into());
+        assert_eq!(app.screen, Screen::Menu);
+
+        // Keys go to the counter, not to the filter it was chosen in.
+        for c in "/count".chars() {
+            app.handle_key_event(KeyCode::Char(c).into());
+        }
+        app.handle_key_event(KeyCode::Enter.into());
+        app.handle_key_event(KeyCode::Enter.into());
+        app.handle_key_event(KeyCode::Right.into());
+        app.handle_key_event(KeyCode::Char('r').into());
+        assert_eq!(app.screen, Screen::Counter);
+        assert_eq!(app.counters.active().value, 0);
+        assert_eq!(app.menu.filter().unwrap().query(), "count");
+    }
+
+    #[test]
+    fn manage_named_counters() {
+        let mut app = App {
+            screen: Screen::Counters,
+            ..App::default()
+        };
+        app.handle_key_event(KeyCode::Char('n').into());
+        for c in "apples".chars() {
+            app.handle_key_event(KeyCode::Char(c).into());
+        }
+        app.handle_key_event(KeyCode::Enter.into());
+        assert_eq!(app.name_input, None);
+        assert_eq!(app.counters.active().name, "apples");
+
+        // Renaming to a taken name keeps the input open and reports why.
+        app.handle_key_event(KeyCode::Char('e').into());
+        for _ in 0..6 {
+            app.handle_key_event(KeyCode::Backspace.into());
+        }
+        for c in "default".chars() {
+            app.handle_key_event(KeyCode::Char(c).into());
+        }
+        app.handle_key_event(KeyCode::Enter.into());
+        assert!(app.name_input.is_some());
+        assert!(app.status.is_some());
+        app.handle_key_event(KeyCode::Esc.into());
+        assert_eq!(app.counters.active().name, "apples");
+
+        app.handle_key_event(KeyCode::Char('d').into());
+        assert_eq!(app.counters.len(), 1);
+        assert_eq!(app.counters.active().name, "default");
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut app = App::default();
+        app.handle_key_event(KeyCode::Up.into());
+        app.handle_key_event(KeyCode::Enter.into());
+        app.handle_key_event(KeyCode::Enter.into());
+        app.handle_key_event(KeyCode::Right.into());
+        app.handle_key_event(KeyCode::Right.into());
+        assert_eq!(app.counters.active().value, 2);
+
+        app.handle_key_event(KeyCode::Char('u').into());
+        assert_eq!(app.counters.active().value, 1);
+        app.handle_key_event(KeyCode::Char('u').into());
+        app.handle_key_event(KeyCode::Char('u').into());
+        assert_eq!(app.screen, Screen::Menu);
+        assert_eq!(app.menu.selected(), 0);
+        app.handle_key_event(KeyCode::Char('u').into());
+        assert_eq!(app.status.as_deref(), Some("nothing to undo"));
+
+        let ctrl_r = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        app.handle_key_event(ctrl_r);
+        app.handle_key_event(ctrl_r);
+        assert_eq!(app.screen, Screen::Counters);
+        assert_eq!(app.counters.active().value, 1);
+    }
+
+    #[test]
+    fn undo_counter_deletion() {
+        let mut app = App {
+            screen: Screen::Counters,
+            ..App::default()
+        };
+        app.handle_key_event(KeyCode::Char('n').into());
+        app.handle_key_event(KeyCode::Char('x').into());
+        app.handle_key_event(KeyCode::Enter.into());
+        app.handle_key_event(KeyCode::Up.into());
+        app.handle_key_event(KeyCode::Char('d').into());
+        assert_eq!(app.counters.active().name, "x");
+
+        app.handle_key_event(KeyCode::Char('u').into());
+        assert_eq!(app.counters.len(), 2);
+        assert_eq!(app.counters.active().name, "default");
+        app.handle_key_event(KeyCode::Char('u').into());
+        assert_eq!(app.counters.len(), 1);
+    }
+
+    #[test]
+    fn submenus_remember_their_selection() {
+        let mut settings = MenuItem::new("Settings");
+        settings.children = vec![MenuItem::new("Display"), MenuItem::new("Network")];
+        let mut app = App::new(Menu {
+            title: "Main".into(),
+            items: vec![MenuItem::new("One"), settings],
+        });
+
+        app.handle_key_event(KeyCode::Down.into());
+        app.handle_key_event(KeyCode::Enter.into());
+        app.handle_key_event(KeyCode::Down.into());
+        assert_eq!(app.menu.breadcrumbs(), ["Main", "Settings"]);
+        assert_eq!(app.menu.selected(), 1);
+
+        app.handle_key_event(KeyCode::Esc.into());
+        assert_eq!(app.menu.breadcrumbs(), ["Main"]);
+        assert_eq!(app.menu.selected(), 1);
+
+        app.handle_key_event(KeyCode::Right.into());
+        assert_eq!(app.menu.selected(), 1);
+
+        // Leaf items and the root level ignore descending and going back.
+        app.handle_key_event(KeyCode::Backspace.into());
+        app.handle_key_event(KeyCode::Left.into());
+        app.handle_key_event(KeyCode::Up.into());
+        app.handle_key_event(KeyCode::Enter.into());
+        assert_eq!(app.menu.breadcrumbs(), ["Main"]);
+        assert_eq!(app.menu.selected(), 0);
+    }
+
+    /// Types `text` one character at a time.
+    fn keys(app: &mut App, text: &str) {
+        for c in text.chars() {
+            app.handle_key_event(KeyCode::Char(c).into());
+        }
+    }
+
+    /// Handles events until every job is done.
+    fn wait_for_jobs(app: &mut App) {
+        while app.jobs.iter().any(Job::is_running) {
+            let event = app.events.recv().unwrap();
+            app.handle_event(event);
+        }
+    }
+
+    #[test]
+    fn enter_runs_the_action() {
+        let item = |label: &str, action| MenuItem {
+            action: Some(action),
+            ..MenuItem::new(label)
+        };
+        let mut app = App::new(Menu {
+            title: "Main".into(),
+            items: vec![
+                item("Echo", Action::Command("echo hi; exit 3".into())),
+                item(
+                    "Dark",
+                    Action::Set {
+                        key: "theme".into(),
+                        value: "dark".into(),
+                    },
+                ),
+                item("Count", Action::Callback("count".into())),
+                item("Print", Action::Print("done".into())),
+            ],
+        });
+        app.register_callback("count", |app| format!("{} settings", app.settings.len()));
+
+        app.handle_key_event(KeyCode::Enter.into());
+        wait_for_jobs(&mut app);
+        assert_eq!(
+            app.status.as_deref(),
+            Some("`echo hi; exit 3` exited with 3: hi")
+        );
+
+        app.handle_key_event(KeyCode::Down.into());
+        app.handle_key_event(KeyCode::Enter.into());
+        assert_eq!(app.settings["theme"], "dark");
+        assert_eq!(app.theme.border, Theme::preset("dark").unwrap().border);
+        app.undo();
+        assert_eq!(app.theme.border, Theme::default().border);
+        app.redo();
+        assert_eq!(app.theme.border, Theme::preset("dark").unwrap().border);
+
+        app.handle_key_event(KeyCode::Down.into());
+        app.handle_key_event(KeyCode::Enter.into());
+        assert_eq!(app.status.as_deref(), Some("1 settings"));
+
+        app.handle_key_event(KeyCode::Down.into());
+        app.handle_key_event(KeyCode::Enter.into());
+        assert_eq!(app.selection().unwrap().value, "done");
+        assert!(app.exit);
+    }
+
+    #[test]
+    fn mouse_selects_and_activates() -> io::Result<()> {
+        let mut app = App::default();
+        let mut terminal = Terminal::new(TestBackend::new(20, 8))?;
+        terminal.draw(|frame| app.draw(frame))?;
+        let mouse = |kind, row| MouseEvent {
+            kind,
+            column: 10,
+            row,
+            modifiers: KeyModifiers::NONE,
+        };
+        let click = mouse(MouseEventKind::Down(MouseButton::Left), 3);
+
+        // The items are drawn from the row below the top border.
+        app.handle_mouse_event(mouse(MouseEventKind::Moved, 2));
+        assert_eq!(app.menu.hovered(), Some(1));
+        app.handle_mouse_event(mouse(MouseEventKind::Moved, 6));
+        assert_eq!(app.menu.hovered(), None);
+
+        app.handle_mouse_event(click);
+        assert_eq!(app.menu.selected(), 2);
+        app.handle_mouse_event(mouse(MouseEventKind::ScrollDown, 0));
+        assert_eq!(app.menu.selected(), 3);
+        app.handle_mouse_event(mouse(MouseEventKind::ScrollUp, 0));
+        assert_eq!(app.menu.selected(), 2);
+
+        let double_click = mouse(MouseEventKind::Down(MouseButton::Left), 4);
+        app.handle_mouse_event(double_click);
+        assert_eq!(app.screen, Screen::Menu);
+        app.handle_mouse_event(double_click);
+        assert_eq!(app.screen, Screen::Counters);
+        Ok(())
+    }
+
+    #[test]
+    fn long_menus_scroll() -> io::Result<()> {
+        let mut app = App::picker((0..100_000).map(|i| i.to_string()).collect());
+        let mut terminal = Terminal::new(TestBackend::new(16, 6))?;
+        let mut draw = |app: &mut App| -> io::Result<Vec<String>> {
+            terminal.draw(|frame| app.draw(frame))?;
+            let buffer = terminal.backend().buffer();
+            Ok((0..buffer.area.height)
+                .map(|y| {
+                    (0..buffer.area.width)
+                        .map(|x| buffer[(x, y)].symbol())
+                        .collect()
+                })
+                .collect())
+        };
+
+        app.handle_key_event(KeyCode::End.into());
+        assert_eq!(app.menu.selected(), 99_999);
+        assert_eq!(
+            draw(&mut app)?,
+            [
+                "┏━━━ Select ━━━┓",
+                "┃     99996    ┃",
+                "┃     99997    ┃",
+                "┃     99998    ┃",
+                "┃     99999    █",
+                "┗ Cancel <Esc> ┛",
+            ]
+        );
+
+        // Moving up within the visible rows does not scroll.
+        app.handle_key_event(KeyCode::Up.into());
+        app.handle_key_event(KeyCode::PageUp.into());
+        assert_eq!(app.menu.selected(), 99_994);
+        draw(&mut app)?;
+        assert_eq!(app.menu.offset(), 99_994);
+        app.handle_key_event(KeyCode::Down.into());
+        draw(&mut app)?;
+        assert_eq!(app.menu.offset(), 99_994);
+
+        app.handle_key_event(KeyCode::Home.into());
+        draw(&mut app)?;
+        app.handle_key_event(KeyCode::PageDown.into());
+        assert_eq!(app.menu.selected(), 4);
+        let lines = draw(&mut app)?;
+        assert_eq!(lines[1], "┃       1      █");
+        assert_eq!(app.menu.position_at(7, 1), Some(1));
+        Ok(())
+    }
+
+    #[test]
+    fn short_areas_drop_the_border() {
+        let mut app = App::picker(["apple", "banana", "grape"].map(String::from).into());
+        app.handle_key_event(KeyCode::Down.into());
+        let mut terminal = Terminal::new(TestBackend::new(12, 2)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let row = |y| (0..12).map(|x| buffer[(x, y)].symbol()).collect::<String>();
+        assert_eq!([row(0), row(1)], ["    apple  █", "   banana  █"]);
+        assert_eq!(app.menu.position_at(4, 1), Some(1));
+
+        app.handle_key_event(KeyCode::Enter.into());
+        assert_eq!(app.summary().as_deref(), Some("Select › banana"));
+    }
+
+    #[test]
+    fn typing_filters_the_menu() {
+        let mut app = App::picker(
+            ["apple", "banana", "grape", "pineapple"]
+                .map(String::from)
+                .into(),
+        );
+        // Bound characters like `j` and `k` type in pickers too.
+        keys(&mut app, "pl");
+        let matches: Vec<_> = app
+            .menu
+            .filter()
+            .unwrap()
+            .matches()
+            .iter()
+            .map(|m| m.index)
+            .collect();
+        assert_eq!(matches, [0, 3]);
+        assert_eq!(app.menu.selected(), 0);
+        app.handle_key_event(KeyCode::Down.into());
+        assert_eq!(app.menu.selected(), 3);
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 20, 5));
+        app.render(buf.area, &mut buf);
+        // The tightest match in "apple" is the second `p` and the `l`.
+        assert_eq!(buf[(9, 1)].symbol(), "p");
+        assert_eq!(buf[(9, 1)].fg, Color::Reset);
+        assert_eq!(buf[(10, 1)].fg, Color::Yellow);
+        assert_eq!(buf[(11, 1)].fg, Color::Yellow);
+
+        keys(&mut app, "x");
+        assert_eq!(app.menu.view_len(), 0);
+        app.handle_key_event(KeyCode::Enter.into());
+        assert_eq!(app.selection(), None);
+
+        // Deleting the whole query closes the filter, keeping the selection.
+        for _ in 0..4 {
+            app.handle_key_event(KeyCode::Backspace.into());
+        }
+        assert_eq!(app.menu.filter(), None);
+        assert_eq!(app.menu.selected(), 0);
+
+        keys(&mut app, "gr");
+        assert_eq!(app.menu.selected(), 2);
+        app.handle_key_event(KeyCode::Enter.into());
+        assert_eq!(app.selection().unwrap().value, "grape");
+
+        let mut app = App::picker(["quince", "kiwi", "hosts"].map(String::from).into());
+        keys(&mut app, "hosts");
+        assert!(!app.exit);
+        assert!(!app.show_history);
+        assert_eq!(app.menu.selected(), 2);
+    }
+
+    #[test]
+    fn redraws_only_on_changes() {
+        let mut app = App::default();
+        let moved = |row| {
+            AppEvent::Terminal(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Moved,
+                column: 0,
+                row,
+                modifiers: KeyModifiers::NONE,
+            }))
+        };
+        assert!(!app.handle_event(AppEvent::Tick));
+        assert!(!app.handle_event(moved(0)));
+        assert!(app.handle_event(AppEvent::Terminal(Event::Key(KeyCode::Down.into()))));
+
+        app.sender().send(Message::Status("fetched".into()));
+        let event = app.events.recv().unwrap();
+        assert!(app.handle_event(event));
+        assert_eq!(app.status.as_deref(), Some("fetched"));
+    }
+
+    #[test]
+    fn signals_quit() {
+        let mut app = App::default();
+        app.handle_event(AppEvent::Signal(libc::SIGHUP));
+        assert!(app.exit);
+        assert_eq!(app.signal, Some(libc::SIGHUP));
+    }
+
+    #[test]
+    fn suspend_and_hand_over_the_terminal() {
+        let mut app = App::new(Menu {
+            title: "Main".into(),
+            items: vec![MenuItem {
+                action: Some(Action::Interactive("exit 3".into())),
+                ..MenuItem::new("Edit")
+            }],
+        });
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL));
+        assert_eq!(app.outside.take(), Some(Outside::Suspend));
+        assert!(!app.handle_event(AppEvent::Signal(libc::SIGTSTP)));
+        assert_eq!(app.outside.take(), Some(Outside::Suspend));
+        assert!(!app.exit);
+
+        app.handle_key_event(KeyCode::Enter.into());
+        let outside = app.outside.take().unwrap();
+        assert_eq!(outside, Outside::Run("exit 3".into()));
+        app.go_outside(outside);
+        assert_eq!(app.status.as_deref(), Some("`exit 3` exited with 3"));
+    }
+
+    #[test]
+    fn commands_run_in_the_background() {
+        let mut app = App::new(Menu {
+            title: "Main".into(),
+            items: vec![MenuItem {
+                action: Some(Action::Command("echo started; sleep 30".into())),
+                ..MenuItem::new("Slow")
+            }],
+        });
+        app.handle_key_event(KeyCode::Enter.into());
+        while app.jobs[0].output.is_empty() {
+            let event = app.events.recv().unwrap();
+            app.handle_event(event);
+        }
+        assert!(app.jobs[0].is_running());
+        let mut buf = Buffer::empty(Rect::new(0, 0, 30, 12));
+        app.render(buf.area, &mut buf);
+        let row = |y| (0..30).map(|x| buf[(x, y)].symbol()).collect::<String>();
+        assert_eq!(row(1), "┃            Slow            ┃");
+        assert!(row(4).starts_with("┏ ⠋ echo started; sleep 30 "));
+        assert_eq!(row(5), "┃started                     ┃");
+
+        // The menu keeps working while the job runs.
+        app.handle_key_event(KeyCode::Char('h').into());
+        assert!(app.show_history);
+
+        app.handle_key_event(KeyCode::Esc.into());
+        wait_for_jobs(&mut app);
+        assert_eq!(
+            app.status.as_deref(),
+            Some("`echo started; sleep 30` was cancelled")
+        );
+
+        app.handle_key_event(KeyCode::Char('o').into());
+        assert_eq!(app.screen, Screen::Output);
+        assert_eq!(app.jobs[0].output, ["started"]);
+        app.handle_key_event(KeyCode::Esc.into());
+        assert_eq!(app.screen, Screen::Menu);
+    }
+
+    #[test]
+    fn output_screen_switches_jobs() {
+        let command = |command: &str| MenuItem {
+            action: Some(Action::Command(command.into())),
+            ..MenuItem::new(command)
+        };
+        let mut app = App::new(Menu {
+            title: "Main".into(),
+            items: vec![command("echo one"), command("echo two")],
+        });
+        app.handle_key_event(KeyCode::Enter.into());
+        wait_for_jobs(&mut app);
+        app.handle_key_event(KeyCode::Down.into());
+        app.handle_key_event(KeyCode::Enter.into());
+        wait_for_jobs(&mut app);
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 40, 5));
+        let mut shown = |app: &mut App| {
+            app.render(buf.area, &mut buf);
+            [0, 1].map(|y| (0..40).map(|x| buf[(x, y)].symbol()).collect::<String>())
+        };
+        app.handle_key_event(KeyCode::Char('o').into());
+        let [title, line] = shown(&mut app);
+        assert!(title.starts_with("┏ 2/2 ━"), "{title}");
+        assert!(title.contains(" echo two succeeded "), "{title}");
+        assert!(line.starts_with("┃two "), "{line}");
+
+        // The first job is as far back as it goes.
+        app.handle_key_event(KeyCode::Left.into());
+        app.handle_key_event(KeyCode::Left.into());
+        let [title, line] = shown(&mut app);
+        assert!(title.starts_with("┏ 1/2 ━"), "{title}");
+        assert!(line.starts_with("┃one "), "{line}");
+
+        // Past the last job, the screen follows new jobs again.
+        app.handle_key_event(KeyCode::Right.into());
+        app.handle_key_event(KeyCode::Right.into());
+        assert_eq!(app.output_job, None);
+        app.handle_key_event(KeyCode::Esc.into());
+        app.handle_key_event(KeyCode::Up.into());
+        app.handle_key_event(KeyCode::Enter.into());
+        wait_for_jobs(&mut app);
+        app.handle_key_event(KeyCode::Char('o').into());
+        let [title, _] = shown(&mut app);
+        assert!(title.starts_with("┏ 3/3 ━"), "{title}");
+    }
+
+    #[test]
+    fn picker_selects_or_cancels() {
+        let mut app = App::picker(vec!["a b".into(), "c".into()]);
+        app.handle_key_event(KeyCode::Down.into());
+        app.handle_key_event(KeyCode::Enter.into());
+        assert_eq!(
+            app.selection(),
+            Some(&Selection {
+                index: 1,
+                id: "1".into(),
+                label: "c".into(),
+                value: "c".into(),
+            })
+        );
+
+        let mut app = App::picker(vec!["a".into()]);
+        app.handle_key_event(KeyCode::Esc.into());
+        assert!(app.exit);
+        assert_eq!(app.selection(), None);
+
+        let mut app = App::picker(vec!["a".into()]);
+        app.handle_key_event(KeyCode::Char('q').into());
+        assert!(app.exit);
+        assert_eq!(app.selection(), None);
+
+        // Once something is typed, `q` is part of the query.
+        let mut app = App::picker(vec!["squid".into()]);
+        keys(&mut app, "sq");
+        assert!(!app.exit);
+        assert_eq!(app.menu.filter().unwrap().query(), "sq");
+    }
+
+    #[test]
+    fn multi_picker_prints_the_checked_items() {
+        let items = || vec!["80".into(), "443".into(), "8080".into()];
+        let mut app = App::multi_picker(items());
+        app.handle_key_event(KeyCode::Char(' ').into());
+        app.handle_key_event(KeyCode::End.into());
+        app.handle_key_event(KeyCode::Tab.into());
+        app.handle_key_event(KeyCode::Up.into());
+        app.handle_key_event(KeyCode::Enter.into());
+        let ports: Vec<u16> = app
+            .selections()
+            .iter()
+            .map(|selection| selection.parse().unwrap())
+            .collect();
+        assert_eq!(ports, [80, 8080]);
+        assert_eq!(app.selections()[1].index, 2);
+        assert_eq!(app.summary().as_deref(), Some("Select › 80, 8080"));
+
+        // Once something is typed, Space is part of the query.
+        let mut app = App::multi_picker(items());
+        keys(&mut app, "8 ");
+        assert_eq!(app.menu.filter().unwrap().query(), "8 ");
+        assert_eq!(app.menu.checked_items().len(), 0);
+
+        // Without any checked, the chosen item is printed.
+        let mut app = App::multi_picker(items());
+        app.handle_key_event(KeyCode::Down.into());
+        app.handle_key_event(KeyCode::Enter.into());
+        assert_eq!(app.selections().len(), 1);
+        assert_eq!(app.selection().unwrap().value, "443");
+    }
+
+    #[test]
+    fn toggles_can_be_undone() {
+        let radio = |label| MenuItem {
+            toggle: Some(Toggle::Radio {
+                group: "size".into(),
+            }),
+            ..MenuItem::new(label)
+        };
+        let mut small = radio("Small");
+        small.checked = true;
+        let mut app = App::new(Menu {
+            title: "Size".into(),
+            items: vec![small, radio("Large")],
+        });
+        app.handle_key_event(KeyCode::Down.into());
+        app.handle_key_event(KeyCode::Char(' ').into());
+        assert_eq!(app.menu.checked(), [1]);
+        assert_eq!(
+            app.history.done().next().unwrap().to_string(),
+            "check Large"
+        );
+
+        app.handle_key_event(KeyCode::Char('u').into());
+        assert_eq!(app.menu.checked(), [0]);
+        assert_eq!(app.menu.selected(), 1);
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL));
+        assert_eq!(app.menu.checked(), [1]);
+    }
+
+    #[test]
+    fn plain_items_ignore_the_checked_ones() {
+        let menu = Menu::parse(
+            r#"
+[[item]]
+label = "Say hello"
+print = "hello"
+
+[[item]]
+label = "Large"
+radio = "size"
+print = "large"
+checked = true
+"#,
+        )
+        .unwrap();
+        let mut app = App::new(menu.clone());
+        app.handle_key_event(KeyCode::Enter.into());
+        let values: Vec<_> = app.selections().iter().map(|s| &*s.value).collect();
+        assert_eq!(values, ["hello"]);
+
+        let mut app = App::new(menu);
+        app.handle_key_event(KeyCode::Down.into());
+        app.handle_key_event(KeyCode::Enter.into());
+        let values: Vec<_> = app.selections().iter().map(|s| &*s.value).collect();
+        assert_eq!(values, ["large"]);
+    }
+}
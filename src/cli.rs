@@ -0,0 +1,292 @@
+//! Command-line arguments.
+
+use std::{fmt, path::PathBuf};
+
+use ratatui::Viewport;
+
+use testo::Selection;
+
+pub const USAGE: &str = "\
+Usage: testo [OPTIONS] [ITEM]...
+
+Shows a menu in the terminal. When ITEMs are given, or stdin is not a
+terminal and no --menu is given, shows them as a picker on /dev/tty and
+prints the chosen item to stdout instead. Exits with 130 when the picker
+is cancelled, and with 128 plus the signal number on SIGTERM, SIGHUP or
+SIGINT. Colors are turned off when NO_COLOR is set.
+
+Typing in a picker filters its items. Until something is typed, q and
+Esc cancel it, and Space checks items in a --multi picker. In a menu,
+keys that are bound to actions, like q or j, are shortcuts instead, and
+/ starts a filter that they can be typed into.
+
+Options:
+  -m, --menu <PATH>        Menu file [default: $XDG_CONFIG_HOME/testo/menu.toml]
+  -t, --title <TEXT>       Title of the root menu
+  -s, --select <INDEX>     Initially selected item, counting from 0
+      --theme <NAME|PATH>  Color theme: default, light, dark, high-contrast,
+                           monochrome or a theme file
+                           [default: $XDG_CONFIG_HOME/testo/theme.toml]
+      --keymap <PATH>      Key bindings file
+                           [default: $XDG_CONFIG_HOME/testo/keymap.toml]
+      --history <LENGTH>   How many changes can be undone [default: 100]
+      --tick-rate <MS>     How often the screen can change on its own
+                           [default: 250]
+      --inline <LINES>     Draw in LINES lines below the prompt and leave
+                           only the choice behind
+      --fullscreen         Draw on the alternate screen (the default)
+      --multi              Pick several items, checked with Space or Tab,
+                           and print each on its own line
+  -o, --output <FORMAT>    How to print the choice: plain, json or index
+                           [default: plain]
+      --record <PATH>      Write every event the app receives to PATH
+      --replay <PATH>      Run on the events in a recording or script
+                           instead of the keyboard, until they run out
+      --replay-speed <N>   Replay N times as fast, or without waiting for 0
+                           [default: 1]
+  -h, --help               Print this help
+  -V, --version            Print the version
+";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// The value of the chosen item.
+    #[default]
+    Plain,
+    /// A JSON object with the index, id, label and value of the chosen item.
+    Json,
+    /// The position of the chosen item in its menu.
+    Index,
+}
+
+impl OutputFormat {
+    pub fn format(self, selection: &Selection) -> String {
+        match self {
+            OutputFormat::Plain => selection.value.clone(),
+            OutputFormat::Index => selection.index.to_string(),
+            OutputFormat::Json => format!(
+                r#"{{"index":{},"id":{},"label":{},"value":{}}}"#,
+                selection.index,
+                json_string(&selection.id),
+                json_string(&selection.label),
+                json_string(&selection.value),
+            ),
+        }
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Run(Box<Cli>),
+    Help,
+    Version,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cli {
+    pub menu: Option<PathBuf>,
+    pub title: Option<String>,
+    pub select: Option<usize>,
+    pub theme: Option<String>,
+    pub keymap: Option<PathBuf>,
+    pub history: Option<usize>,
+    /// Milliseconds between ticks.
+    pub tick_rate: Option<u64>,
+    pub viewport: Viewport,
+    pub output: OutputFormat,
+    /// Whether the picker checks items to choose several.
+    pub multi: bool,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub replay_speed: Option<u32>,
+    /// Items to pick from instead of showing the menu.
+    pub items: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliError(String);
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl Cli {
+    /// Parses the arguments, not including the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, CliError> {
+        let mut cli = Cli::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                cli.items.extend(args);
+                break;
+            }
+            if !arg.starts_with('-') || arg == "-" {
+                cli.items.push(arg);
+                continue;
+            }
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = |name: &str| {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| CliError(format!("{name} needs a value")))
+            };
+            match flag {
+                "-h" | "--help" => return Ok(Command::Help),
+                "-V" | "--version" => return Ok(Command::Version),
+                "-m" | "--menu" => cli.menu = Some(value(flag)?.into()),
+                "-t" | "--title" => cli.title = Some(value(flag)?),
+                "-s" | "--select" => cli.select = Some(number(flag, &value(flag)?)?),
+                "--theme" => cli.theme = Some(value(flag)?),
+                "--keymap" => cli.keymap = Some(value(flag)?.into()),
+                "--history" => cli.history = Some(number(flag, &value(flag)?)?),
+                "--tick-rate" => {
+                    let tick_rate = number(flag, &value(flag)?)?;
+                    if tick_rate == 0 {
+                        return Err(CliError("--tick-rate needs at least 1 ms".into()));
+                    }
+                    cli.tick_rate = Some(tick_rate);
+                }
+                "--inline" => {
+                    let lines = number(flag, &value(flag)?)?;
+                    if lines == 0 {
+                        return Err(CliError("--inline needs at least 1 line".into()));
+                    }
+                    cli.viewport = Viewport::Inline(lines);
+                }
+                "--fullscreen" => cli.viewport = Viewport::Fullscreen,
+                "--multi" => cli.multi = true,
+                "-o" | "--output" => {
+                    cli.output = match value(flag)?.as_str() {
+                        "plain" => OutputFormat::Plain,
+                        "json" => OutputFormat::Json,
+                        "index" => OutputFormat::Index,
+                        other => {
+                            return Err(CliError(format!(
+                                "invalid output format `{other}`, expected plain, json or index"
+                            )));
+                        }
+                    }
+                }
+                "--record" => cli.record = Some(value(flag)?.into()),
+                "--replay" => cli.replay = Some(value(flag)?.into()),
+                "--replay-speed" => cli.replay_speed = Some(number(flag, &value(flag)?)?),
+                _ => return Err(CliError(format!("unknown option `{flag}`"))),
+            }
+        }
+        if cli.replay_speed.is_some() && cli.replay.is_none() {
+            return Err(CliError("--replay-speed needs --replay".into()));
+        }
+        Ok(Command::Run(Box::new(cli)))
+    }
+}
+
+fn number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, CliError> {
+    value
+        .parse()
+        .map_err(|_| CliError(format!("{flag} expects a number, got `{value}`")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, CliError> {
+        Cli::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_flags_and_items() {
+        let Ok(Command::Run(cli)) = parse(&[
+            "--menu=menu.toml",
+            "-t",
+            "Pick one",
+            "--inline",
+            "5",
+            "-o",
+            "json",
+            "--replay",
+            "bug.script",
+            "--replay-speed=0",
+            "--multi",
+            "a",
+            "--",
+            "--not-a-flag",
+        ]) else {
+            panic!("expected to parse");
+        };
+        assert_eq!(cli.menu, Some("menu.toml".into()));
+        assert_eq!(cli.title.as_deref(), Some("Pick one"));
+        assert_eq!(cli.viewport, Viewport::Inline(5));
+        assert_eq!(cli.output, OutputFormat::Json);
+        assert_eq!(cli.replay, Some("bug.script".into()));
+        assert_eq!(cli.replay_speed, Some(0));
+        assert!(cli.multi);
+        assert_eq!(cli.items, ["a", "--not-a-flag"]);
+
+        assert_eq!(parse(&["a", "--help"]), Ok(Command::Help));
+        assert_eq!(parse(&["-V"]), Ok(Command::Version));
+    }
+
+    #[test]
+    fn reports_invalid_flags() {
+        assert_eq!(
+            parse(&["--nope"]).unwrap_err().to_string(),
+            "unknown option `--nope`"
+        );
+        assert_eq!(
+            parse(&["--select", "x"]).unwrap_err().to_string(),
+            "--select expects a number, got `x`"
+        );
+        assert_eq!(
+            parse(&["--menu"]).unwrap_err().to_string(),
+            "--menu needs a value"
+        );
+        assert!(parse(&["--output", "yaml"]).is_err());
+        assert!(parse(&["--tick-rate", "0"]).is_err());
+        assert_eq!(
+            parse(&["--replay-speed", "10"]).unwrap_err().to_string(),
+            "--replay-speed needs --replay"
+        );
+    }
+
+    #[test]
+    fn formats_json_output() {
+        let selection = Selection {
+            index: 2,
+            id: "say-hi".into(),
+            label: "Say \"hi\"".into(),
+            value: "hi\n".into(),
+        };
+        assert_eq!(
+            OutputFormat::Json.format(&selection),
+            r#"{"index":2,"id":"say-hi","label":"Say \"hi\"","value":"hi\n"}"#
+        );
+        assert_eq!(OutputFormat::Index.format(&selection), "2");
+    }
+}
@@ -0,0 +1,260 @@
+//! The state of the named counters.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CounterConfig {
+    /// How much increment and decrement change the value by.
+    pub step: i64,
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+}
+
+impl Default for CounterConfig {
+    fn default() -> Self {
+        Self {
+            step: 1,
+            min: None,
+            max: None,
+        }
+    }
+}
+
+impl CounterConfig {
+    fn clamp(&self, value: i64) -> i64 {
+        let value = self.min.map_or(value, |min| value.max(min));
+        self.max.map_or(value, |max| value.min(max))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counter {
+    pub name: String,
+    pub value: i64,
+}
+
+/// A list of named counters, one of which is active. There is always at
+/// least one counter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counters {
+    counters: Vec<Counter>,
+    active: usize,
+    config: CounterConfig,
+}
+
+impl Default for Counters {
+    fn default() -> Self {
+        Self {
+            counters: vec![Counter {
+                name: "default".into(),
+                value: 0,
+            }],
+            active: 0,
+            config: CounterConfig::default(),
+        }
+    }
+}
+
+impl Counters {
+    /// Restores saved counters. Returns `None` if `counters` is empty.
+    pub fn from_saved(counters: Vec<Counter>, active: usize) -> Option<Self> {
+        if counters.is_empty() {
+            return None;
+        }
+        Some(Self {
+            active: active.min(counters.len() - 1),
+            counters,
+            config: CounterConfig::default(),
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Counter> {
+        self.counters.iter()
+    }
+
+    // There is always a counter, so there is no `is_empty`.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.counters.len()
+    }
+
+    pub fn active_index(&self) -> usize {
+        self.active
+    }
+
+    pub fn active(&self) -> &Counter {
+        &self.counters[self.active]
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.active = index.min(self.counters.len() - 1);
+    }
+
+    /// Switches to `config` for the changes that follow. Values outside the
+    /// new bounds are left as they are until they are changed.
+    pub fn configure(&mut self, config: CounterConfig) {
+        self.config = config;
+    }
+
+    /// Adds the step, but never takes a value above the maximum further
+    /// down.
+    pub fn increment(&mut self) {
+        let counter = &mut self.counters[self.active];
+        let value = self
+            .config
+            .clamp(counter.value.saturating_add(self.config.step));
+        counter.value = value.max(counter.value);
+    }
+
+    /// Subtracts the step, but never takes a value below the minimum
+    /// further up.
+    pub fn decrement(&mut self) {
+        let counter = &mut self.counters[self.active];
+        let value = self
+            .config
+            .clamp(counter.value.saturating_sub(self.config.step));
+        counter.value = value.min(counter.value);
+    }
+
+    /// Sets the value back to 0, or to the closest bound if 0 is outside.
+    pub fn reset(&mut self) {
+        self.counters[self.active].value = self.config.clamp(0);
+    }
+
+    /// Adds a counter after the others and makes it active.
+    pub fn create(&mut self, name: &str) -> Result<(), String> {
+        let name = self.validate_name(name, None)?;
+        self.counters.push(Counter {
+            name,
+            value: self.config.clamp(0),
+        });
+        self.active = self.counters.len() - 1;
+        Ok(())
+    }
+
+    pub fn rename(&mut self, index: usize, name: &str) -> Result<(), String> {
+        let name = self.validate_name(name, Some(index))?;
+        self.counters[index].name = name;
+        Ok(())
+    }
+
+    /// Sets a value as is, even outside the bounds, e.g. to undo a change.
+    pub fn set_value(&mut self, index: usize, value: i64) {
+        self.counters[index].value = value;
+    }
+
+    /// Puts back a deleted counter and makes it active.
+    pub fn insert(&mut self, index: usize, counter: Counter) {
+        let index = index.min(self.counters.len());
+        self.counters.insert(index, counter);
+        self.active = index;
+    }
+
+    pub fn delete(&mut self, index: usize) -> Result<Counter, String> {
+        if self.counters.len() == 1 {
+            return Err("cannot delete the last counter".into());
+        }
+        let counter = self.counters.remove(index);
+        if self.active > index || self.active == self.counters.len() {
+            self.active -= 1;
+        }
+        Ok(counter)
+    }
+
+    fn validate_name(&self, name: &str, renaming: Option<usize>) -> Result<String, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("counter names cannot be empty".into());
+        }
+        let taken = self
+            .counters
+            .iter()
+            .enumerate()
+            .any(|(i, counter)| counter.name == name && Some(i) != renaming);
+        if taken {
+            return Err(format!("there already is a counter named `{name}`"));
+        }
+        Ok(name.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stays_within_bounds() {
+        let mut counters = Counters::default();
+        counters.configure(CounterConfig {
+            step: 4,
+            min: Some(2),
+            max: Some(10),
+        });
+        // Values only move into the bounds when they change.
+        assert_eq!(counters.active().value, 0);
+
+        counters.increment();
+        counters.increment();
+        counters.increment();
+        assert_eq!(counters.active().value, 10);
+        counters.decrement();
+        assert_eq!(counters.active().value, 6);
+        counters.reset();
+        assert_eq!(counters.active().value, 2);
+    }
+
+    #[test]
+    fn keeps_values_outside_other_bounds() {
+        let mut counters = Counters::from_saved(
+            vec![Counter {
+                name: "big".into(),
+                value: 15,
+            }],
+            0,
+        )
+        .unwrap();
+        counters.configure(CounterConfig {
+            max: Some(10),
+            ..CounterConfig::default()
+        });
+        assert_eq!(counters.active().value, 15);
+        counters.increment();
+        assert_eq!(counters.active().value, 15);
+        counters.decrement();
+        assert_eq!(counters.active().value, 10);
+        counters.configure(CounterConfig::default());
+        counters.increment();
+        assert_eq!(counters.active().value, 11);
+    }
+
+    #[test]
+    fn saturates_without_bounds() {
+        let mut counters = Counters::from_saved(
+            vec![Counter {
+                name: "big".into(),
+                value: i64::MAX,
+            }],
+            0,
+        )
+        .unwrap();
+        counters.increment();
+        assert_eq!(counters.active().value, i64::MAX);
+    }
+
+    #[test]
+    fn create_rename_and_delete() {
+        let mut counters = Counters::default();
+        counters.increment();
+        counters.create(" apples ").unwrap();
+        assert_eq!(counters.active().name, "apples");
+        assert_eq!(counters.active().value, 0);
+
+        assert!(counters.create("apples").is_err());
+        assert!(counters.rename(0, "apples").is_err());
+        assert!(counters.rename(1, "apples").is_ok());
+        counters.rename(1, "pears").unwrap();
+
+        counters.select(0);
+        counters.delete(1).unwrap();
+        assert_eq!(counters.active().value, 1);
+        assert!(counters.delete(0).is_err());
+    }
+}
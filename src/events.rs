@@ -0,0 +1,342 @@
+//! The event loop's inputs: terminal events, ticks, signals and messages
+//! from background tasks, merged into one channel.
+
+use std::{
+    io, mem,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{self, Event};
+use signal_hook::{
+    consts::{SIGHUP, SIGINT, SIGQUIT, SIGTERM, SIGTSTP},
+    iterator::{Handle, Signals},
+};
+
+/// The signals the app handles itself. SIGTSTP suspends it and the others
+/// make it quit, in both cases after restoring the terminal.
+pub const SIGNALS: [i32; 4] = [SIGTERM, SIGHUP, SIGINT, SIGTSTP];
+
+/// How often the input thread checks whether it should stop.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppEvent {
+    Terminal(Event),
+    /// Sent every tick, for things that change over time.
+    Tick,
+    /// The process received one of the [`SIGNALS`].
+    Signal(i32),
+    Message(Message),
+}
+
+/// What background tasks can tell the app.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    /// Show this in the status line.
+    Status(String),
+    /// A line printed by a background job.
+    JobOutput { job: usize, line: String },
+    /// One of the job's output streams reached its end.
+    JobClosed { job: usize },
+}
+
+/// Sends messages to the event loop from any thread.
+#[derive(Debug, Clone)]
+pub struct MessageSender(Sender<io::Result<AppEvent>>);
+
+impl MessageSender {
+    /// Returns `false` if the app has quit.
+    pub fn send(&self, message: Message) -> bool {
+        self.0.send(Ok(AppEvent::Message(message))).is_ok()
+    }
+}
+
+#[derive(Debug)]
+pub struct Events {
+    sender: Sender<io::Result<AppEvent>>,
+    receiver: Receiver<io::Result<AppEvent>>,
+    tick_rate: Duration,
+    next_tick: Instant,
+    /// Set while a replay sends the events, ticks included.
+    replaying: Arc<AtomicBool>,
+}
+
+impl Default for Events {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(250))
+    }
+}
+
+impl Events {
+    pub fn new(tick_rate: Duration) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            sender,
+            receiver,
+            tick_rate,
+            next_tick: Instant::now() + tick_rate,
+            replaying: Arc::default(),
+        }
+    }
+
+    pub fn sender(&self) -> MessageSender {
+        MessageSender(self.sender.clone())
+    }
+
+    /// Starts reading terminal events on a thread, until the returned guard
+    /// is dropped.
+    pub fn read_terminal(&self) -> InputThread {
+        let sender = self.sender.clone();
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = thread::spawn({
+            let stop = stop.clone();
+            move || forward_terminal(&sender, &stop)
+        });
+        InputThread {
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// Sends `events` at the times they happened, `speed` times as fast, or
+    /// without waiting for a speed of 0. Until they are sent there are no
+    /// other ticks and the terminal is not read, then it is read as by
+    /// [`Events::read_terminal`], until the returned guard is dropped.
+    pub fn replay(&self, events: Vec<(Duration, AppEvent)>, speed: u32) -> InputThread {
+        let sender = self.sender.clone();
+        let stop = Arc::new(AtomicBool::new(false));
+        self.replaying.store(true, Ordering::Relaxed);
+        let handle = thread::spawn({
+            let stop = stop.clone();
+            let replaying = self.replaying.clone();
+            move || {
+                let started = Instant::now();
+                for (time, event) in events {
+                    if speed > 0 {
+                        let due = started + time / speed;
+                        while !stop.load(Ordering::Relaxed) && Instant::now() < due {
+                            thread::sleep(POLL_INTERVAL.min(due - Instant::now()));
+                        }
+                    }
+                    if stop.load(Ordering::Relaxed) || sender.send(Ok(event)).is_err() {
+                        return;
+                    }
+                }
+                replaying.store(false, Ordering::Relaxed);
+                forward_terminal(&sender, &stop);
+            }
+        });
+        InputThread {
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// Whether a replay is still sending its events.
+    pub fn is_replaying(&self) -> bool {
+        self.replaying.load(Ordering::Relaxed)
+    }
+
+    /// Turns the [`SIGNALS`] into events instead of letting them stop the
+    /// process, until the returned guard is dropped.
+    pub fn watch_signals(&self) -> io::Result<SignalThread> {
+        let mut signals = Signals::new(SIGNALS)?;
+        let sender = self.sender.clone();
+        let handle = signals.handle();
+        let thread = thread::spawn(move || {
+            for signal in &mut signals {
+                if sender.send(Ok(AppEvent::Signal(signal))).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(SignalThread {
+            handle,
+            thread: Some(thread),
+        })
+    }
+
+    /// Waits for the next event, which is a tick if nothing else happens
+    /// before it is due.
+    pub fn recv(&mut self) -> io::Result<AppEvent> {
+        loop {
+            let timeout = self.next_tick.saturating_duration_since(Instant::now());
+            match self.receiver.recv_timeout(timeout) {
+                Ok(event) => return event,
+                Err(RecvTimeoutError::Timeout) => {
+                    // Skip ticks that were missed rather than catching up.
+                    self.next_tick = Instant::now() + self.tick_rate;
+                    // Replays bring their own ticks.
+                    if !self.is_replaying() {
+                        return Ok(AppEvent::Tick);
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => unreachable!("we hold a sender"),
+            }
+        }
+    }
+}
+
+/// Sends terminal events until `stop` is set or the app is gone.
+fn forward_terminal(sender: &Sender<io::Result<AppEvent>>, stop: &AtomicBool) {
+    while !stop.load(Ordering::Relaxed) {
+        let event = match event::poll(POLL_INTERVAL) {
+            Ok(false) => continue,
+            Ok(true) => event::read(),
+            Err(error) => Err(error),
+        };
+        let failed = event.is_err();
+        if sender.send(event.map(AppEvent::Terminal)).is_err() || failed {
+            break;
+        }
+    }
+}
+
+/// Stops the input thread when dropped, so that nothing reads from the
+/// terminal once it has been restored.
+#[derive(Debug)]
+pub struct InputThread {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Drop for InputThread {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Leaves the keyboard's signals to a command that has the terminal, the way
+/// shells do, until the returned guard is dropped: Ctrl-C and Ctrl-\\ only
+/// reach the command, and Ctrl-Z stops the app along with it. Call it once
+/// the command is started, as it would inherit the ignored signals.
+pub fn hand_over_signals() -> io::Result<HandedOverSignals> {
+    let mut guard = HandedOverSignals(Vec::new());
+    for (signal, handler) in [
+        (SIGINT, libc::SIG_IGN),
+        (SIGQUIT, libc::SIG_IGN),
+        (SIGTSTP, libc::SIG_DFL),
+    ] {
+        // SAFETY: the action is zeroed apart from the handler, which is one
+        // of the dispositions that take no function, and the previous action
+        // is put back as it was.
+        unsafe {
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = handler;
+            let mut previous: libc::sigaction = mem::zeroed();
+            if libc::sigaction(signal, &action, &mut previous) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            guard.0.push((signal, previous));
+        }
+    }
+    Ok(guard)
+}
+
+/// Puts back the signal handlers that [`hand_over_signals`] replaced when
+/// dropped.
+pub struct HandedOverSignals(Vec<(i32, libc::sigaction)>);
+
+impl Drop for HandedOverSignals {
+    fn drop(&mut self) {
+        for (signal, action) in &self.0 {
+            // SAFETY: the action is the one that was in place before.
+            unsafe {
+                libc::sigaction(*signal, action, std::ptr::null_mut());
+            }
+        }
+    }
+}
+
+/// Stops watching for signals when dropped.
+#[derive(Debug)]
+pub struct SignalThread {
+    handle: Handle,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for SignalThread {
+    fn drop(&mut self) {
+        self.handle.close();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_and_ticks() -> io::Result<()> {
+        let mut events = Events::new(Duration::from_millis(10));
+        let sender = events.sender();
+        let task = thread::spawn(move || sender.send(Message::Status("done".into())));
+        assert!(task.join().unwrap());
+
+        assert!(matches!(
+            events.recv()?,
+            AppEvent::Message(Message::Status(status)) if status == "done"
+        ));
+        assert!(matches!(events.recv()?, AppEvent::Tick));
+        Ok(())
+    }
+
+    #[test]
+    fn replays_bring_their_own_ticks() -> io::Result<()> {
+        let mut events = Events::new(Duration::from_millis(10));
+        let focus = AppEvent::Terminal(Event::FocusGained);
+        let replay = vec![
+            (Duration::ZERO, AppEvent::Tick),
+            (Duration::from_millis(40), focus.clone()),
+        ];
+        let started = Instant::now();
+        let _input = events.replay(replay, 2);
+        assert_eq!(events.recv()?, AppEvent::Tick);
+        // No ticks of our own in the 20 ms until the next event.
+        assert_eq!(events.recv()?, focus);
+        assert!(started.elapsed() >= Duration::from_millis(20));
+        Ok(())
+    }
+
+    #[test]
+    fn signals_become_events() -> io::Result<()> {
+        let mut events = Events::new(Duration::from_millis(10));
+        let _signals = events.watch_signals()?;
+        signal_hook::low_level::raise(SIGTERM)?;
+        // Other tests may send signals too, so look for ours.
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if let AppEvent::Signal(SIGTERM) = events.recv()? {
+                return Ok(());
+            }
+        }
+        panic!("no signal event");
+    }
+
+    #[test]
+    fn handed_over_signals_are_ignored() -> io::Result<()> {
+        let mut events = Events::new(Duration::from_millis(10));
+        let _signals = events.watch_signals()?;
+        {
+            let _handed_over = hand_over_signals()?;
+            // Neither kills the test nor reaches the app.
+            signal_hook::low_level::raise(SIGINT)?;
+        }
+        let deadline = Instant::now() + Duration::from_millis(100);
+        while Instant::now() < deadline {
+            assert_ne!(events.recv()?, AppEvent::Signal(SIGINT));
+        }
+        Ok(())
+    }
+}
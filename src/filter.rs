@@ -0,0 +1,205 @@
+//! Fuzzy filtering of menu items.
+//!
+//! A query matches a label when its characters appear in the label in
+//! order, ignoring case unless the query has an uppercase letter. Matches
+//! are ranked by how tightly the characters cluster and whether they start
+//! words.
+
+use crate::menu::MenuItem;
+
+/// An item that matches the query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    /// The position of the item in its menu.
+    pub index: usize,
+    pub score: i64,
+    /// The positions of the matched characters in the label, counted in
+    /// chars.
+    pub positions: Vec<usize>,
+}
+
+/// The query being typed and the items that match it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    query: String,
+    /// The matches for every prefix of the query, from the empty query up.
+    /// A longer query only matches items that a shorter one matched, so
+    /// typing narrows down the last results and deleting pops them.
+    layers: Vec<Vec<Match>>,
+    /// The position of the selected item among the matches.
+    pub selected: usize,
+}
+
+impl Filter {
+    /// A filter with an empty query, which matches every item that can be
+    /// selected, in order.
+    pub fn new(items: &[MenuItem]) -> Self {
+        let all = (0..items.len())
+            .filter(|&index| items[index].is_selectable())
+            .map(|index| Match {
+                index,
+                score: 0,
+                positions: Vec::new(),
+            })
+            .collect();
+        Self {
+            query: String::new(),
+            layers: vec![all],
+            selected: 0,
+        }
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// How many items the empty query matches.
+    pub fn total(&self) -> usize {
+        self.layers[0].len()
+    }
+
+    /// The matching items, best first.
+    pub fn matches(&self) -> &[Match] {
+        self.layers.last().expect("there is always the empty query")
+    }
+
+    /// Adds `c` to the query and selects the best match.
+    pub fn push(&mut self, c: char, items: &[MenuItem]) {
+        self.query.push(c);
+        let mut matches: Vec<Match> = self
+            .matches()
+            .iter()
+            .filter_map(|candidate| {
+                let (score, positions) = fuzzy_match(&self.query, &items[candidate.index].label)?;
+                Some(Match {
+                    index: candidate.index,
+                    score,
+                    positions,
+                })
+            })
+            .collect();
+        matches.sort_unstable_by_key(|m| (-m.score, m.index));
+        self.layers.push(matches);
+        self.selected = 0;
+    }
+
+    /// Removes the last character of the query. Returns `false` if the query
+    /// was already empty.
+    pub fn pop(&mut self) -> bool {
+        if self.query.pop().is_none() {
+            return false;
+        }
+        self.layers.pop();
+        self.selected = 0;
+        true
+    }
+}
+
+const MATCH: i64 = 16;
+const CONSECUTIVE: i64 = 8;
+const WORD_START: i64 = 8;
+const FIRST_CHAR: i64 = 4;
+const GAP: i64 = 1;
+
+/// Matches `query` against `text`, returning the score and the positions of
+/// the matched characters.
+///
+/// Finds the first place where the whole query matches, then walks back
+/// from its end to the latest start, which gives the shortest match there.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let case_sensitive = query.chars().any(char::is_uppercase);
+    let eq = |a: char, b: char| {
+        if case_sensitive {
+            a == b
+        } else {
+            a == b || a.to_lowercase().eq(b.to_lowercase())
+        }
+    };
+    let query: Vec<char> = query.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    if query.is_empty() {
+        return Some((0, Vec::new()));
+    }
+
+    let mut end = None;
+    let mut q = 0;
+    for (i, &c) in text.iter().enumerate() {
+        if eq(c, query[q]) {
+            q += 1;
+            if q == query.len() {
+                end = Some(i);
+                break;
+            }
+        }
+    }
+    let end = end?;
+
+    let mut positions = vec![0; query.len()];
+    let mut q = query.len();
+    for i in (0..=end).rev() {
+        if eq(text[i], query[q - 1]) {
+            q -= 1;
+            positions[q] = i;
+            if q == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut score = 0;
+    for (n, &position) in positions.iter().enumerate() {
+        score += MATCH;
+        if position == 0 {
+            score += FIRST_CHAR + WORD_START;
+        } else if !text[position - 1].is_alphanumeric() {
+            score += WORD_START;
+        }
+        if n > 0 {
+            let gap = position - positions[n - 1] - 1;
+            score += if gap == 0 {
+                CONSECUTIVE
+            } else {
+                -GAP * gap as i64
+            };
+        }
+    }
+    Some((score, positions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_in_order() {
+        assert_eq!(fuzzy_match("cnt", "Counters").unwrap().1, [0, 3, 4]);
+        assert_eq!(fuzzy_match("oo", "foo boo").unwrap().1, [1, 2]);
+        assert_eq!(fuzzy_match("tc", "Counters"), None);
+        // An uppercase letter makes the query case sensitive.
+        assert_eq!(fuzzy_match("C", "counters"), None);
+    }
+
+    #[test]
+    fn ranks_and_narrows_down() {
+        let items: Vec<_> = ["Set Theme", "Settings", "Reset", "Exit"]
+            .into_iter()
+            .map(MenuItem::new)
+            .collect();
+        let mut filter = Filter::new(&items);
+        let indices =
+            |filter: &Filter| filter.matches().iter().map(|m| m.index).collect::<Vec<_>>();
+
+        filter.push('s', &items);
+        assert_eq!(indices(&filter), [0, 1, 2]);
+        filter.push('t', &items);
+        assert_eq!(indices(&filter), [0, 1, 2]);
+        filter.push('g', &items);
+        assert_eq!(indices(&filter), [1]);
+
+        assert!(filter.pop());
+        assert_eq!(filter.query(), "st");
+        assert!(filter.pop() && filter.pop());
+        assert_eq!(indices(&filter), [0, 1, 2, 3]);
+        assert!(!filter.pop());
+    }
+}
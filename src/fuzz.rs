@@ -0,0 +1,242 @@
+//! Throws arbitrary input at the app and checks that it holds together: it
+//! never panics, the selection stays on an item, and drawing stays inside
+//! its area. The property tests below feed it random bytes, and the fuzz
+//! target in `fuzz/` feeds it whatever libFuzzer comes up with, through the
+//! `fuzzing` feature that builds this module outside of tests.
+
+use crossterm::event::{Event, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
+use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+
+use crate::{
+    App,
+    events::AppEvent,
+    keymap::Key,
+    menu::{Action, Menu, MenuItem, Toggle},
+};
+
+/// The keys the input is made of: everything the default keymap binds, and
+/// some typing.
+const KEYS: &str = "up down left right pageup pagedown home end enter esc backspace tab \
+    backtab space delete q j k h l g G r n d a u o / x + - 0 ctrl-r ctrl-z ctrl-s";
+
+/// A menu with the shapes that navigation gets wrong: a submenu with one
+/// item, one that scrolls, one where nothing can be selected, items that
+/// cannot be selected between the others, checkboxes and radio buttons, and
+/// labels that are hard to filter.
+fn menu() -> Menu {
+    let many = (1..=30)
+        .map(|n| MenuItem::new(format!("Item {n}")))
+        .collect();
+    let radio = |label, checked| MenuItem {
+        toggle: Some(Toggle::Radio {
+            group: "radio".into(),
+        }),
+        checked,
+        ..MenuItem::new(label)
+    };
+    Menu {
+        title: "Fuzz".into(),
+        items: vec![
+            MenuItem::header("Sections"),
+            MenuItem {
+                children: vec![MenuItem::new("Only")],
+                ..MenuItem::new("One")
+            },
+            MenuItem {
+                children: vec![MenuItem::separator(), MenuItem::header("Nothing")],
+                ..MenuItem::new("None")
+            },
+            MenuItem::separator(),
+            MenuItem {
+                children: many,
+                ..MenuItem::new("Many")
+            },
+            MenuItem::new(""),
+            MenuItem::new("ünïcödé 表"),
+            MenuItem {
+                disabled: Some("never".into()),
+                ..MenuItem::new("Disabled")
+            },
+            MenuItem {
+                toggle: Some(Toggle::Checkbox),
+                action: Some(Action::Print("checked".into())),
+                ..MenuItem::new("Checkbox")
+            },
+            radio("Radio", true),
+            radio("Other radio", false),
+            MenuItem {
+                action: Some(Action::Counter(Default::default())),
+                ..MenuItem::new("Counter")
+            },
+        ],
+    }
+}
+
+/// Plays `data` as a sequence of events and checks the app after each, once
+/// with the fixture menu and once with an empty one, as embedding code can
+/// make. Panics if something does not hold.
+pub fn check(data: &[u8]) {
+    let empty = || Menu {
+        title: "Empty".into(),
+        items: Vec::new(),
+    };
+    for make_menu in [menu, empty] {
+        let mut bytes = data.iter().copied();
+        let mut next = || bytes.next().unwrap_or_default();
+        let mut app = App::new(make_menu());
+        let mut area = Rect::new(0, 0, 40, 12);
+        while let Some(event) = decode(&mut next, &mut area) {
+            app.handle_event(event);
+            check_render(&mut app, area);
+            check_state(&app);
+            if app.has_quit() {
+                app = App::new(make_menu());
+            }
+        }
+    }
+}
+
+/// Makes an event of the next bytes, or of the next few for resizes and
+/// mouse events. Returns `None` at the end of the input.
+fn decode(next: &mut impl FnMut() -> u8, area: &mut Rect) -> Option<AppEvent> {
+    let event = match next() {
+        // The input ends with zeros.
+        0 => return None,
+        byte @ 1..0xe0 => {
+            let keys: Vec<_> = KEYS.split_whitespace().collect();
+            let key = Key::parse(keys[usize::from(byte) % keys.len()]).ok()?;
+            Event::Key(key.into())
+        }
+        0xe0..0xe8 => {
+            *area = Rect::new(0, 0, u16::from(next() % 80), u16::from(next() % 40));
+            Event::Resize(area.width, area.height)
+        }
+        0xe8..0xf0 => Event::Mouse(MouseEvent {
+            kind: match next() % 4 {
+                0 => MouseEventKind::Moved,
+                1 => MouseEventKind::ScrollUp,
+                2 => MouseEventKind::ScrollDown,
+                _ => MouseEventKind::Down(crossterm::event::MouseButton::Left),
+            },
+            column: u16::from(next()),
+            row: u16::from(next()),
+            modifiers: KeyModifiers::NONE,
+        }),
+        0xf0..0xf8 => {
+            let text = String::from_utf8_lossy(&[next(), next(), next()]).into_owned();
+            Event::Paste(text)
+        }
+        0xf8..0xfc => return Some(AppEvent::Tick),
+        _ => Event::Key(KeyEvent::from(crossterm::event::KeyCode::Char(char::from(
+            next(),
+        )))),
+    };
+    Some(AppEvent::Terminal(event))
+}
+
+fn check_state(app: &App) {
+    let menu = &app.menu;
+    let len = menu.items().len();
+    if len == 0 {
+        assert_eq!(menu.selected(), 0);
+        assert_eq!(menu.selected_item(), None);
+        assert_eq!(menu.view_len(), 0);
+    }
+    assert!(
+        menu.selected() < len.max(1),
+        "selected {} of {len}",
+        menu.selected()
+    );
+    if let Some(hovered) = menu.hovered() {
+        assert!(hovered < len, "hovered {hovered} of {len}");
+    }
+    let radios: Vec<_> = menu
+        .items()
+        .iter()
+        .filter(|item| matches!(item.toggle, Some(Toggle::Radio { .. })))
+        .collect();
+    if !radios.is_empty() {
+        let checked = radios.iter().filter(|item| item.checked).count();
+        assert_eq!(checked, 1, "{checked} radio buttons are checked");
+    }
+    if menu.items().iter().any(MenuItem::is_selectable) {
+        assert!(
+            menu.items()[menu.selected()].is_selectable(),
+            "selected {} cannot be selected",
+            menu.selected()
+        );
+    }
+    let view_len = menu.view_len();
+    if view_len > 0 {
+        let position = menu.view_position();
+        assert!(position < view_len, "position {position} of {view_len}");
+        assert_eq!(menu.view_item(position), menu.selected());
+        assert!(
+            menu.offset() < view_len,
+            "offset {} of {view_len}",
+            menu.offset()
+        );
+    }
+    assert!(app.counters.active_index() < app.counters.len());
+}
+
+/// Draws the app in `area` of a larger buffer and checks that nothing is
+/// drawn around it.
+fn check_render(app: &mut App, area: Rect) {
+    let outer = Rect::new(0, 0, area.width + 2, area.height + 2);
+    let mut buf = Buffer::empty(outer);
+    app.render(Rect { x: 1, y: 1, ..area }, &mut buf);
+    let empty = Buffer::empty(outer);
+    for x in outer.left()..outer.right() {
+        for y in [outer.top(), outer.bottom() - 1] {
+            assert_eq!(buf[(x, y)], empty[(x, y)], "drew outside at {x}, {y}");
+        }
+    }
+    for y in outer.top()..outer.bottom() {
+        for x in [outer.left(), outer.right() - 1] {
+            assert_eq!(buf[(x, y)], empty[(x, y)], "drew outside at {x}, {y}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A small xorshift generator, so that failures can be reproduced from
+    /// the seed.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn bytes(&mut self, len: usize) -> Vec<u8> {
+            (0..len).map(|_| (self.next() % 255) as u8 + 1).collect()
+        }
+    }
+
+    #[test]
+    fn random_input_holds_together() {
+        for seed in 1..=64 {
+            let mut rng = Rng(seed);
+            let len = (rng.next() % 200) as usize;
+            let data = rng.bytes(len);
+            let result = std::panic::catch_unwind(|| check(&data));
+            assert!(result.is_ok(), "failed for seed {seed}: {data:?}");
+        }
+    }
+
+    #[test]
+    fn edge_cases() {
+        // Tiny and empty areas, and a filter that matches nothing.
+        check(&[0xe0, 1, 1, 2, 1, 0xe0, 0, 0, 2, 1]);
+        check(&[29, 0xff, b'z', 0xff, b'z', 2, 1, 3, 4, 5, 6, 9]);
+        // Into the one-item submenu, then everywhere in it.
+        check(&[9, 1, 2, 5, 6, 7, 8, 10, 10]);
+    }
+}
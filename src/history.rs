@@ -0,0 +1,160 @@
+//! Undo and redo of state changes.
+
+use std::{collections::VecDeque, fmt};
+
+use crate::counter::Counter;
+
+/// A reversible change to the app state. Each change holds enough to be
+/// applied in either direction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// The selection moved within the menu level at `stack`.
+    MenuMove {
+        stack: Vec<usize>,
+        from: usize,
+        to: usize,
+    },
+    CounterValue {
+        index: usize,
+        from: i64,
+        to: i64,
+    },
+    CounterRename {
+        index: usize,
+        from: String,
+        to: String,
+    },
+    CounterCreate {
+        index: usize,
+        counter: Counter,
+    },
+    CounterDelete {
+        index: usize,
+        counter: Counter,
+    },
+    Setting {
+        key: String,
+        from: Option<String>,
+        to: String,
+    },
+    /// The checkbox or radio button at `index` of the menu level at `stack`
+    /// was toggled, changing which items of the level are checked.
+    Toggle {
+        stack: Vec<usize>,
+        index: usize,
+        label: String,
+        from: Vec<usize>,
+        to: Vec<usize>,
+    },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::MenuMove { from, to, .. } => write!(f, "move {from} → {to}"),
+            Change::CounterValue { from, to, .. } => write!(f, "counter {from} → {to}"),
+            Change::CounterRename { from, to, .. } => write!(f, "rename {from} → {to}"),
+            Change::CounterCreate { counter, .. } => write!(f, "create {}", counter.name),
+            Change::CounterDelete { counter, .. } => write!(f, "delete {}", counter.name),
+            Change::Setting { key, to, .. } => write!(f, "set {key} = {to}"),
+            Change::Toggle {
+                index, label, to, ..
+            } if to.contains(index) => write!(f, "check {label}"),
+            Change::Toggle { label, .. } => write!(f, "uncheck {label}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct History {
+    /// Changes that can be undone, oldest first.
+    done: VecDeque<Change>,
+    /// Changes that can be redone, most recently undone last.
+    undone: Vec<Change>,
+    limit: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(100)
+    }
+}
+
+impl History {
+    /// Creates a history that remembers at most `limit` changes.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            done: VecDeque::new(),
+            undone: Vec::new(),
+            limit,
+        }
+    }
+
+    /// Records a change that was just made, forgetting the oldest change if
+    /// the history is full and everything that was undone.
+    pub fn record(&mut self, change: Change) {
+        self.undone.clear();
+        if self.limit == 0 {
+            return;
+        }
+        if self.done.len() == self.limit {
+            self.done.pop_front();
+        }
+        self.done.push_back(change);
+    }
+
+    /// Returns the change to revert, if any.
+    pub fn undo(&mut self) -> Option<&Change> {
+        let change = self.done.pop_back()?;
+        self.undone.push(change);
+        self.undone.last()
+    }
+
+    /// Returns the change to apply again, if any.
+    pub fn redo(&mut self) -> Option<&Change> {
+        let change = self.undone.pop()?;
+        self.done.push_back(change);
+        self.done.back()
+    }
+
+    /// The changes that can be undone, most recent first.
+    pub fn done(&self) -> impl Iterator<Item = &Change> {
+        self.done.iter().rev()
+    }
+
+    /// The changes that can be redone, next to redo first.
+    pub fn undone(&self) -> impl Iterator<Item = &Change> {
+        self.undone.iter().rev()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(to: i64) -> Change {
+        Change::CounterValue {
+            index: 0,
+            from: to - 1,
+            to,
+        }
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut history = History::new(2);
+        history.record(change(1));
+        history.record(change(2));
+        history.record(change(3));
+        assert_eq!(history.done().collect::<Vec<_>>(), [&change(3), &change(2)]);
+
+        assert_eq!(history.undo(), Some(&change(3)));
+        assert_eq!(history.undo(), Some(&change(2)));
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.redo(), Some(&change(2)));
+        assert_eq!(history.undone().collect::<Vec<_>>(), [&change(3)]);
+
+        history.record(change(4));
+        assert_eq!(history.redo(), None);
+    }
+}
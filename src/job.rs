@@ -0,0 +1,306 @@
+//! Commands that run in the background while the menu stays usable.
+
+use std::{
+    fmt,
+    io::{BufRead, BufReader, Read},
+    os::unix::process::CommandExt,
+    process::{Child, Command, ExitStatus, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::events::{Message, MessageSender};
+
+/// How long the output of a command that exited may take to arrive. Whatever
+/// it left running in the background can hold the output open for longer.
+const OUTPUT_GRACE: Duration = Duration::from_millis(500);
+
+/// How long a cancelled command has to stop before it is killed.
+const STOP_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobState {
+    Running,
+    Exited(i32),
+    /// Killed by a signal we did not send.
+    Killed,
+    Cancelled,
+    /// The command could not be started.
+    Failed(String),
+}
+
+impl fmt::Display for JobState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobState::Running => f.write_str("running"),
+            JobState::Exited(0) => f.write_str("succeeded"),
+            JobState::Exited(code) => write!(f, "exited with {code}"),
+            JobState::Killed => f.write_str("killed by a signal"),
+            JobState::Cancelled => f.write_str("cancelled"),
+            JobState::Failed(error) => write!(f, "failed to start: {error}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Job {
+    pub command: String,
+    /// Everything the command printed, stdout and stderr interleaved as
+    /// they arrived.
+    pub output: Vec<String>,
+    pub state: JobState,
+    started: Instant,
+    finished: Option<Instant>,
+    child: Option<Child>,
+    /// How the process exited and when.
+    exited: Option<(ExitStatus, Instant)>,
+    /// Output streams that are still open. A job is done once its process
+    /// exited and all its output has arrived, or [`OUTPUT_GRACE`] passed.
+    open_streams: usize,
+    cancelled: bool,
+}
+
+impl Job {
+    /// Starts `command` with `sh -c`. Its output is sent to the app as
+    /// messages tagged with `id`.
+    pub fn spawn(id: usize, command: &str, sender: &MessageSender) -> Self {
+        let mut job = Self {
+            command: command.into(),
+            output: Vec::new(),
+            state: JobState::Running,
+            started: Instant::now(),
+            finished: None,
+            child: None,
+            exited: None,
+            open_streams: 0,
+            cancelled: false,
+        };
+        let spawned = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // A group of its own, so that cancelling also stops whatever the
+            // shell started.
+            .process_group(0)
+            .spawn();
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(error) => {
+                job.state = JobState::Failed(error.to_string());
+                job.finished = Some(Instant::now());
+                return job;
+            }
+        };
+        if let Some(stdout) = child.stdout.take() {
+            forward_lines(id, stdout, sender.clone());
+            job.open_streams += 1;
+        }
+        if let Some(stderr) = child.stderr.take() {
+            forward_lines(id, stderr, sender.clone());
+            job.open_streams += 1;
+        }
+        job.child = Some(child);
+        job
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state == JobState::Running
+    }
+
+    /// How long the job has been running, or ran for.
+    pub fn elapsed(&self) -> Duration {
+        self.finished.unwrap_or_else(Instant::now) - self.started
+    }
+
+    /// Notes that one of the output streams reached its end.
+    pub fn close_stream(&mut self) {
+        self.open_streams = self.open_streams.saturating_sub(1);
+    }
+
+    /// Checks whether the job is done. Returns `true` only the first time it
+    /// is.
+    pub fn poll(&mut self) -> bool {
+        if !self.is_running() {
+            return false;
+        }
+        if self.exited.is_none() {
+            let Some(child) = &mut self.child else {
+                return false;
+            };
+            match child.try_wait() {
+                Ok(Some(status)) => self.exited = Some((status, Instant::now())),
+                Ok(None) => return false,
+                Err(error) => {
+                    self.state = JobState::Failed(error.to_string());
+                    self.finished = Some(Instant::now());
+                    return true;
+                }
+            }
+        }
+        let Some((status, exited)) = self.exited else {
+            return false;
+        };
+        if self.open_streams > 0 && exited.elapsed() < OUTPUT_GRACE {
+            return false;
+        }
+        self.state = match status.code() {
+            Some(code) => JobState::Exited(code),
+            None if self.cancelled => JobState::Cancelled,
+            None => JobState::Killed,
+        };
+        self.finished = Some(exited);
+        self.child = None;
+        true
+    }
+
+    /// Asks the command and everything it started to stop.
+    pub fn cancel(&mut self) {
+        if self.child.is_some() {
+            self.cancelled = true;
+            self.signal_group(libc::SIGTERM);
+        }
+    }
+
+    /// Waits for the process of a cancelled job to exit, so that it is not
+    /// left behind, and kills its group if it takes longer than
+    /// [`STOP_TIMEOUT`].
+    pub fn reap(&mut self) {
+        if self.exited.is_some() {
+            return;
+        }
+        let Some(child) = &mut self.child else {
+            return;
+        };
+        let deadline = Instant::now() + STOP_TIMEOUT;
+        while let Ok(None) = child.try_wait() {
+            if Instant::now() >= deadline {
+                self.signal_group(libc::SIGKILL);
+                if let Some(child) = &mut self.child {
+                    let _ = child.wait();
+                }
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Sends `signal` to the command and everything it started.
+    fn signal_group(&self, signal: i32) {
+        let Some(pid) = self
+            .child
+            .as_ref()
+            .and_then(|child| i32::try_from(child.id()).ok())
+        else {
+            return;
+        };
+        // SAFETY: kill has no memory safety requirements. The negative pid
+        // addresses the job's process group.
+        unsafe {
+            libc::kill(-pid, signal);
+        }
+    }
+
+    /// Describes how the job went, for the status line.
+    pub fn summary(&self) -> String {
+        let command = &self.command;
+        let last_line = self
+            .output
+            .iter()
+            .rfind(|line| !line.trim().is_empty())
+            .map_or("", String::as_str);
+        match &self.state {
+            JobState::Running => format!("`{command}` is running"),
+            JobState::Exited(0) => format!("`{command}` succeeded: {last_line}"),
+            JobState::Exited(code) => format!("`{command}` exited with {code}: {last_line}"),
+            JobState::Killed => format!("`{command}` was killed by a signal"),
+            JobState::Cancelled => format!("`{command}` was cancelled"),
+            JobState::Failed(error) => format!("`{command}` failed to start: {error}"),
+        }
+    }
+}
+
+/// Sends every line read from `stream` to the app, then a message that the
+/// stream is closed.
+fn forward_lines(id: usize, stream: impl Read + Send + 'static, sender: MessageSender) {
+    thread::spawn(move || {
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if !sender.send(Message::JobOutput { job: id, line }) {
+                return;
+            }
+        }
+        sender.send(Message::JobClosed { job: id });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::events::{AppEvent, Events};
+
+    /// Feeds the job its messages until it is done.
+    fn wait(job: &mut Job, events: &mut Events) -> io::Result<()> {
+        while !job.poll() {
+            match events.recv()? {
+                AppEvent::Message(Message::JobOutput { line, .. }) => job.output.push(line),
+                AppEvent::Message(Message::JobClosed { .. }) => job.close_stream(),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn runs_and_collects_output() -> io::Result<()> {
+        let mut events = Events::new(Duration::from_millis(10));
+        let mut job = Job::spawn(0, "echo one; echo two >&2; exit 3", &events.sender());
+        wait(&mut job, &mut events)?;
+        assert_eq!(job.state, JobState::Exited(3));
+        job.output.sort();
+        assert_eq!(job.output, ["one", "two"]);
+        Ok(())
+    }
+
+    #[test]
+    fn cancel_stops_the_whole_group() -> io::Result<()> {
+        let mut events = Events::new(Duration::from_millis(10));
+        let mut job = Job::spawn(0, "sleep 30; echo late", &events.sender());
+        job.cancel();
+        wait(&mut job, &mut events)?;
+        assert_eq!(job.state, JobState::Cancelled);
+        assert!(job.elapsed() < Duration::from_secs(30));
+        assert_eq!(job.summary(), "`sleep 30; echo late` was cancelled");
+        Ok(())
+    }
+
+    #[test]
+    fn background_children_do_not_keep_it_running() -> io::Result<()> {
+        let mut events = Events::new(Duration::from_millis(10));
+        let mut job = Job::spawn(0, "sleep 3 & echo started", &events.sender());
+        wait(&mut job, &mut events)?;
+        assert_eq!(job.state, JobState::Exited(0));
+        assert_eq!(job.output, ["started"]);
+        assert!(job.elapsed() < Duration::from_secs(3));
+        Ok(())
+    }
+
+    #[test]
+    fn reap_kills_what_ignores_cancelling() -> io::Result<()> {
+        let mut events = Events::new(Duration::from_millis(10));
+        let mut job = Job::spawn(0, "trap '' TERM; sleep 30", &events.sender());
+        // Give the shell time to set the trap.
+        thread::sleep(Duration::from_millis(100));
+        job.cancel();
+        job.reap();
+        wait(&mut job, &mut events)?;
+        assert_eq!(job.state, JobState::Cancelled);
+        assert!(job.elapsed() < Duration::from_secs(30));
+        Ok(())
+    }
+}
@@ -0,0 +1,758 @@
+//! Key bindings.
+//!
+//! Keys are bound to named actions per screen. A keymap file starts from a
+//! preset and rebinds actions in sections named after the screens, with
+//! `[global]` for actions that work everywhere:
+//!
+//! ```toml
+//! preset = "vim"
+//!
+//! [global]
+//! quit = ["q", "ctrl-c"]
+//!
+//! [counter]
+//! increment = ["right", "+"]
+//! ```
+//!
+//! Binding an action replaces all of its keys from the preset; an empty list
+//! unbinds it.
+
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{
+    menu,
+    toml::{self, ConfigError, Entry, Value},
+};
+
+/// The screen a binding applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    Global,
+    Menu,
+    Counters,
+    Counter,
+    /// The output of a background job.
+    Output,
+}
+
+impl Context {
+    const ALL: [Context; 5] = [
+        Context::Global,
+        Context::Menu,
+        Context::Counters,
+        Context::Counter,
+        Context::Output,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Context::Global => "global",
+            Context::Menu => "menu",
+            Context::Counters => "counters",
+            Context::Counter => "counter",
+            Context::Output => "output",
+        }
+    }
+
+    /// The actions that can be bound in this context.
+    fn actions(self) -> &'static [KeyAction] {
+        use KeyAction::*;
+        match self {
+            Context::Global => &[Quit, Undo, Redo, History, Cancel, Output, Suspend],
+            Context::Menu => &[
+                Up, Down, PageUp, PageDown, First, Last, Activate, Toggle, Open, Back, Filter,
+            ],
+            Context::Counters => &[Up, Down, Open, New, Rename, Delete, Back],
+            Context::Counter => &[Decrement, Increment, Reset, Back],
+            Context::Output => &[
+                Up, Down, PageUp, PageDown, First, Last, Previous, Next, Back,
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    Quit,
+    Undo,
+    Redo,
+    /// Show or hide the history panel.
+    History,
+    /// Stop the running background job.
+    Cancel,
+    /// Show the output of the last background job.
+    Output,
+    /// Give the terminal back to the shell until the app is resumed.
+    Suspend,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    /// Select the first item.
+    First,
+    /// Select the last item.
+    Last,
+    /// Run the selected item's action.
+    Activate,
+    /// Check or uncheck the selected checkbox or radio button.
+    Toggle,
+    Open,
+    Back,
+    /// Start typing a query to filter the menu.
+    Filter,
+    /// Show the output of the job started before the one shown.
+    Previous,
+    /// Show the output of the job started after the one shown.
+    Next,
+    Decrement,
+    Increment,
+    Reset,
+    New,
+    Rename,
+    Delete,
+}
+
+impl KeyAction {
+    const ALL: [KeyAction; 26] = [
+        KeyAction::Quit,
+        KeyAction::Undo,
+        KeyAction::Redo,
+        KeyAction::History,
+        KeyAction::Cancel,
+        KeyAction::Output,
+        KeyAction::Suspend,
+        KeyAction::Up,
+        KeyAction::Down,
+        KeyAction::PageUp,
+        KeyAction::PageDown,
+        KeyAction::First,
+        KeyAction::Last,
+        KeyAction::Activate,
+        KeyAction::Toggle,
+        KeyAction::Open,
+        KeyAction::Back,
+        KeyAction::Filter,
+        KeyAction::Previous,
+        KeyAction::Next,
+        KeyAction::Decrement,
+        KeyAction::Increment,
+        KeyAction::Reset,
+        KeyAction::New,
+        KeyAction::Rename,
+        KeyAction::Delete,
+    ];
+
+    /// The name used in keymap files.
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyAction::Quit => "quit",
+            KeyAction::Undo => "undo",
+            KeyAction::Redo => "redo",
+            KeyAction::History => "history",
+            KeyAction::Cancel => "cancel",
+            KeyAction::Output => "output",
+            KeyAction::Suspend => "suspend",
+            KeyAction::Up => "up",
+            KeyAction::Down => "down",
+            KeyAction::PageUp => "page-up",
+            KeyAction::PageDown => "page-down",
+            KeyAction::First => "first",
+            KeyAction::Last => "last",
+            KeyAction::Activate => "activate",
+            KeyAction::Toggle => "toggle",
+            KeyAction::Open => "open",
+            KeyAction::Back => "back",
+            KeyAction::Filter => "filter",
+            KeyAction::Previous => "previous",
+            KeyAction::Next => "next",
+            KeyAction::Decrement => "decrement",
+            KeyAction::Increment => "increment",
+            KeyAction::Reset => "reset",
+            KeyAction::New => "new",
+            KeyAction::Rename => "rename",
+            KeyAction::Delete => "delete",
+        }
+    }
+
+    /// The name shown in the instructions.
+    pub fn label(self) -> &'static str {
+        match self {
+            KeyAction::Quit => "Quit",
+            KeyAction::Undo => "Undo",
+            KeyAction::Redo => "Redo",
+            KeyAction::History => "History",
+            KeyAction::Cancel => "Cancel",
+            KeyAction::Output => "Output",
+            KeyAction::Suspend => "Suspend",
+            KeyAction::Up => "Up",
+            KeyAction::Down => "Down",
+            KeyAction::PageUp => "Page up",
+            KeyAction::PageDown => "Page down",
+            KeyAction::First => "First",
+            KeyAction::Last => "Last",
+            KeyAction::Activate => "Select",
+            KeyAction::Toggle => "Toggle",
+            KeyAction::Open => "Open",
+            KeyAction::Back => "Back",
+            KeyAction::Filter => "Filter",
+            KeyAction::Previous => "Previous",
+            KeyAction::Next => "Next",
+            KeyAction::Decrement => "Decrement",
+            KeyAction::Increment => "Increment",
+            KeyAction::Reset => "Reset",
+            KeyAction::New => "New",
+            KeyAction::Rename => "Rename",
+            KeyAction::Delete => "Delete",
+        }
+    }
+}
+
+/// A key with its modifiers. Shift is folded into the character for
+/// character keys, so `Q` matches whether or not the terminal reports shift.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let modifiers = match code {
+            KeyCode::Char(_) | KeyCode::BackTab => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+
+    /// Parses keys like `q`, `Q`, `ctrl-r`, `alt-enter`, `pagedown` or `f2`.
+    pub fn parse(key: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = key;
+        loop {
+            let lower = rest.to_ascii_lowercase();
+            let modifier = [
+                ("ctrl-", KeyModifiers::CONTROL),
+                ("alt-", KeyModifiers::ALT),
+                ("shift-", KeyModifiers::SHIFT),
+            ]
+            .into_iter()
+            .find(|(prefix, _)| lower.starts_with(prefix) && lower.len() > prefix.len());
+            match modifier {
+                Some((prefix, modifier)) => {
+                    modifiers |= modifier;
+                    rest = &rest[prefix.len()..];
+                }
+                None => break,
+            }
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "space" => KeyCode::Char(' '),
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=24) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key `{key}`")),
+                },
+            },
+        };
+        Ok(Self::new(code, modifiers))
+    }
+
+    /// The key as [`Key::parse`] reads it, or `None` for keys it does not
+    /// know.
+    pub fn name(self) -> Option<String> {
+        let mut name = String::new();
+        for (modifier, prefix) in [
+            (KeyModifiers::CONTROL, "ctrl-"),
+            (KeyModifiers::ALT, "alt-"),
+            (KeyModifiers::SHIFT, "shift-"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                name.push_str(prefix);
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => name.push_str("space"),
+            KeyCode::Char(c) => name.push(c),
+            KeyCode::F(n) => name.push_str(&format!("f{n}")),
+            code => name.push_str(match code {
+                KeyCode::Enter => "enter",
+                KeyCode::Esc => "esc",
+                KeyCode::Tab => "tab",
+                KeyCode::BackTab => "backtab",
+                KeyCode::Backspace => "backspace",
+                KeyCode::Delete => "delete",
+                KeyCode::Insert => "insert",
+                KeyCode::Home => "home",
+                KeyCode::End => "end",
+                KeyCode::PageUp => "pageup",
+                KeyCode::PageDown => "pagedown",
+                KeyCode::Up => "up",
+                KeyCode::Down => "down",
+                KeyCode::Left => "left",
+                KeyCode::Right => "right",
+                _ => return None,
+            }),
+        }
+        Some(name)
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+impl From<Key> for KeyEvent {
+    fn from(key: Key) -> Self {
+        KeyEvent::new(key.code, key.modifiers)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("^")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("M-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            f.write_str("S-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::Esc => f.write_str("Esc"),
+            KeyCode::PageUp => f.write_str("PgUp"),
+            KeyCode::PageDown => f.write_str("PgDn"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Binding {
+    context: Context,
+    key: Key,
+    action: KeyAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+type Preset = &'static [(Context, KeyAction, &'static [&'static str])];
+
+const DEFAULT: Preset = &[
+    (Context::Global, KeyAction::Quit, &["q"]),
+    (Context::Global, KeyAction::Undo, &["u"]),
+    (Context::Global, KeyAction::Redo, &["ctrl-r"]),
+    (Context::Global, KeyAction::History, &["h"]),
+    (Context::Global, KeyAction::Cancel, &["ctrl-c"]),
+    (Context::Global, KeyAction::Output, &["o"]),
+    (Context::Global, KeyAction::Suspend, &["ctrl-z"]),
+    (Context::Menu, KeyAction::Up, &["up", "k"]),
+    (Context::Menu, KeyAction::Down, &["down", "j"]),
+    (Context::Menu, KeyAction::PageUp, &["pageup"]),
+    (Context::Menu, KeyAction::PageDown, &["pagedown"]),
+    (Context::Menu, KeyAction::First, &["home"]),
+    (Context::Menu, KeyAction::Last, &["end"]),
+    (Context::Menu, KeyAction::Activate, &["enter"]),
+    (Context::Menu, KeyAction::Toggle, &["space", "tab"]),
+    (Context::Menu, KeyAction::Open, &["right"]),
+    (
+        Context::Menu,
+        KeyAction::Back,
+        &["esc", "left", "backspace"],
+    ),
+    (Context::Menu, KeyAction::Filter, &["/"]),
+    (Context::Counters, KeyAction::Up, &["up", "k"]),
+    (Context::Counters, KeyAction::Down, &["down", "j"]),
+    (Context::Counters, KeyAction::Open, &["enter"]),
+    (Context::Counters, KeyAction::New, &["n"]),
+    (Context::Counters, KeyAction::Rename, &["e", "f2"]),
+    (Context::Counters, KeyAction::Delete, &["d", "delete"]),
+    (Context::Counters, KeyAction::Back, &["esc", "backspace"]),
+    (Context::Counter, KeyAction::Decrement, &["left"]),
+    (Context::Counter, KeyAction::Increment, &["right"]),
+    (Context::Counter, KeyAction::Reset, &["r"]),
+    (Context::Counter, KeyAction::Back, &["esc", "backspace"]),
+    (Context::Output, KeyAction::Up, &["up", "k"]),
+    (Context::Output, KeyAction::Down, &["down", "j"]),
+    (Context::Output, KeyAction::PageUp, &["pageup"]),
+    (Context::Output, KeyAction::PageDown, &["pagedown"]),
+    (Context::Output, KeyAction::First, &["home"]),
+    (Context::Output, KeyAction::Last, &["end"]),
+    (Context::Output, KeyAction::Previous, &["left"]),
+    (Context::Output, KeyAction::Next, &["right"]),
+    (Context::Output, KeyAction::Back, &["esc", "backspace"]),
+];
+
+/// Rebinds the default keymap so that `h` and `l` move left and right.
+const VIM: Preset = &[
+    (Context::Global, KeyAction::History, &["H"]),
+    (Context::Menu, KeyAction::PageUp, &["pageup", "ctrl-u"]),
+    (Context::Menu, KeyAction::PageDown, &["pagedown", "ctrl-d"]),
+    (Context::Menu, KeyAction::First, &["home", "g"]),
+    (Context::Menu, KeyAction::Last, &["end", "G"]),
+    (Context::Menu, KeyAction::Open, &["right", "l"]),
+    (
+        Context::Menu,
+        KeyAction::Back,
+        &["esc", "left", "h", "backspace"],
+    ),
+    (Context::Counters, KeyAction::Open, &["enter", "l"]),
+    (
+        Context::Counters,
+        KeyAction::Back,
+        &["esc", "h", "backspace"],
+    ),
+    (Context::Counter, KeyAction::Decrement, &["left", "h"]),
+    (Context::Counter, KeyAction::Increment, &["right", "l"]),
+    (Context::Counter, KeyAction::Back, &["esc", "backspace"]),
+    (Context::Output, KeyAction::Previous, &["left", "h"]),
+    (Context::Output, KeyAction::Next, &["right", "l"]),
+];
+
+/// Rebinds the default keymap to use emacs-style control keys.
+const EMACS: Preset = &[
+    (Context::Global, KeyAction::Quit, &["q", "ctrl-g"]),
+    (Context::Global, KeyAction::Undo, &["u", "ctrl-_"]),
+    (Context::Menu, KeyAction::Up, &["up", "ctrl-p"]),
+    (Context::Menu, KeyAction::Down, &["down", "ctrl-n"]),
+    (Context::Menu, KeyAction::PageUp, &["pageup", "alt-v"]),
+    (Context::Menu, KeyAction::PageDown, &["pagedown", "ctrl-v"]),
+    (Context::Menu, KeyAction::First, &["home", "alt-<"]),
+    (Context::Menu, KeyAction::Last, &["end", "alt->"]),
+    (Context::Menu, KeyAction::Open, &["right", "ctrl-f"]),
+    (
+        Context::Menu,
+        KeyAction::Back,
+        &["esc", "left", "ctrl-b", "backspace"],
+    ),
+    (Context::Menu, KeyAction::Filter, &["/", "ctrl-s"]),
+    (Context::Counters, KeyAction::Up, &["up", "ctrl-p"]),
+    (Context::Counters, KeyAction::Down, &["down", "ctrl-n"]),
+    (Context::Counter, KeyAction::Decrement, &["left", "ctrl-b"]),
+    (Context::Counter, KeyAction::Increment, &["right", "ctrl-f"]),
+    (Context::Output, KeyAction::Previous, &["left", "ctrl-b"]),
+    (Context::Output, KeyAction::Next, &["right", "ctrl-f"]),
+];
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Self {
+            bindings: Vec::new(),
+        };
+        keymap.apply(DEFAULT);
+        keymap
+    }
+}
+
+impl Keymap {
+    /// One of the built-in keymaps: `default`, `vim` or `emacs`.
+    pub fn preset(name: &str) -> Option<Self> {
+        let mut keymap = Self::default();
+        match name {
+            "default" => {}
+            "vim" => keymap.apply(VIM),
+            "emacs" => keymap.apply(EMACS),
+            _ => return None,
+        }
+        Some(keymap)
+    }
+
+    fn apply(&mut self, preset: Preset) {
+        for &(context, action, keys) in preset {
+            let keys = keys
+                .iter()
+                .map(|key| Key::parse(key).expect("presets only use valid keys"));
+            self.bind(context, action, keys);
+        }
+    }
+
+    /// Replaces the keys bound to `action` in `context`.
+    fn bind(&mut self, context: Context, action: KeyAction, keys: impl IntoIterator<Item = Key>) {
+        self.bindings
+            .retain(|binding| binding.context != context || binding.action != action);
+        self.bindings.extend(keys.into_iter().map(|key| Binding {
+            context,
+            key,
+            action,
+        }));
+    }
+
+    /// Loads the keymap from `path`, or from the user's config directory
+    /// when no path is given. Falls back to the default keymap when there is
+    /// no keymap file in the config directory.
+    pub fn load(path: Option<&Path>) -> io::Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match default_path().filter(|path| path.exists()) {
+                Some(path) => path,
+                None => return Ok(Self::default()),
+            },
+        };
+        let source = toml::read(&path)?;
+        Self::parse(&source).map_err(|error| error.in_file(&path).into())
+    }
+
+    pub fn parse(source: &str) -> Result<Self, ConfigError> {
+        let root = toml::parse(source)?;
+        let mut keymap = match root.get("preset") {
+            Some(entry) => {
+                let name = entry.string()?;
+                Self::preset(&name)
+                    .ok_or_else(|| entry.error("expected `default`, `vim` or `emacs`"))?
+            }
+            None => Self::default(),
+        };
+        for entry in &root.entries {
+            if entry.key == "preset" {
+                continue;
+            }
+            let Some(context) = Context::ALL.into_iter().find(|c| c.name() == entry.key) else {
+                return Err(entry.unknown_key());
+            };
+            let Value::Table(table) = &entry.value else {
+                return Err(entry.invalid_type("a table"));
+            };
+            for entry in &table.entries {
+                let action = context
+                    .actions()
+                    .iter()
+                    .copied()
+                    .find(|action| action.name() == entry.key)
+                    .ok_or_else(|| {
+                        if KeyAction::ALL
+                            .iter()
+                            .any(|action| action.name() == entry.key)
+                        {
+                            entry.error(&format!("cannot be bound in [{}]", context.name()))
+                        } else {
+                            entry.error("unknown action")
+                        }
+                    })?;
+                keymap.bind(context, action, parse_keys(entry)?);
+            }
+        }
+        Ok(keymap)
+    }
+
+    /// The action bound to `key` on the screen `context`, falling back to
+    /// the global bindings.
+    pub fn action(&self, context: Context, key: impl Into<Key>) -> Option<KeyAction> {
+        let key = key.into();
+        [context, Context::Global].into_iter().find_map(|context| {
+            self.bindings
+                .iter()
+                .find(|binding| binding.context == context && binding.key == key)
+                .map(|binding| binding.action)
+        })
+    }
+
+    /// The first key bound to `action` on the screen `context`.
+    pub fn key(&self, context: Context, action: KeyAction) -> Option<Key> {
+        [context, Context::Global].into_iter().find_map(|context| {
+            self.bindings
+                .iter()
+                .find(|binding| binding.context == context && binding.action == action)
+                .map(|binding| binding.key)
+        })
+    }
+
+    /// Describes every key that is bound to more than one action on the
+    /// same screen, including screen keys that hide a global binding.
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        for (i, binding) in self.bindings.iter().enumerate() {
+            for other in &self.bindings[..i] {
+                if binding.key != other.key || binding.action == other.action {
+                    continue;
+                }
+                if binding.context == other.context {
+                    conflicts.push(format!(
+                        "`{}` is bound to both `{}` and `{}` in [{}]",
+                        binding.key,
+                        other.action.name(),
+                        binding.action.name(),
+                        binding.context.name()
+                    ));
+                } else if let Some((global, local)) = match (binding.context, other.context) {
+                    (Context::Global, _) => Some((binding, other)),
+                    (_, Context::Global) => Some((other, binding)),
+                    _ => None,
+                } {
+                    conflicts.push(format!(
+                        "`{}` is bound to `{}` in [{}], which hides `{}` in [global]",
+                        local.key,
+                        local.action.name(),
+                        local.context.name(),
+                        global.action.name()
+                    ));
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+fn parse_keys(entry: &Entry) -> Result<Vec<Key>, ConfigError> {
+    let keys = match &entry.value {
+        Value::String(key) => vec![key.as_str()],
+        Value::Array(values) => values
+            .iter()
+            .map(|value| match value {
+                Value::String(key) => Ok(key.as_str()),
+                _ => Err(entry.invalid_type("a key or a list of keys")),
+            })
+            .collect::<Result<_, _>>()?,
+        _ => return Err(entry.invalid_type("a key or a list of keys")),
+    };
+    keys.into_iter()
+        .map(|key| Key::parse(key).map_err(|message| entry.error(&message)))
+        .collect()
+}
+
+/// `$XDG_CONFIG_HOME/testo/keymap.toml`, or `~/.config/testo/keymap.toml`.
+pub fn default_path() -> Option<PathBuf> {
+    menu::config_dir().map(|dir| dir.join("keymap.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_keys() {
+        let key = |code, modifiers| Ok(Key::new(code, modifiers));
+        assert_eq!(Key::parse("q"), key(KeyCode::Char('q'), KeyModifiers::NONE));
+        assert_eq!(
+            Key::parse("shift-q"),
+            key(KeyCode::Char('Q'), KeyModifiers::NONE)
+        );
+        assert_eq!(Key::parse("-"), key(KeyCode::Char('-'), KeyModifiers::NONE));
+        assert_eq!(
+            Key::parse("Ctrl-Alt-PageDown"),
+            key(KeyCode::PageDown, KeyModifiers::CONTROL | KeyModifiers::ALT)
+        );
+        assert_eq!(Key::parse("f12"), key(KeyCode::F(12), KeyModifiers::NONE));
+        assert!(Key::parse("hyper-x").is_err());
+
+        assert_eq!(Key::parse("ctrl-r").unwrap().to_string(), "^r");
+        assert_eq!(Key::parse("ctrl-R").unwrap().to_string(), "^R");
+        // Vim binds `g` and `G` to different actions.
+        assert_eq!(Key::parse("g").unwrap().to_string(), "g");
+        assert_eq!(Key::parse("G").unwrap().to_string(), "G");
+        assert_eq!(Key::parse("left").unwrap().to_string(), "Left");
+
+        for name in [
+            "q",
+            "Q",
+            "-",
+            "space",
+            "ctrl-alt-pagedown",
+            "shift-tab",
+            "f12",
+        ] {
+            assert_eq!(Key::parse(name).unwrap().name().as_deref(), Some(name));
+        }
+        let caps_lock = Key::new(KeyCode::CapsLock, KeyModifiers::NONE);
+        assert_eq!(caps_lock.name(), None);
+    }
+
+    #[test]
+    fn default_keymap_follows_vim_for_j_and_k() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.action(Context::Menu, KeyEvent::from(KeyCode::Char('j'))),
+            Some(KeyAction::Down)
+        );
+        assert_eq!(
+            keymap.action(Context::Menu, KeyEvent::from(KeyCode::Char('k'))),
+            Some(KeyAction::Up)
+        );
+        assert_eq!(
+            keymap.action(Context::Counter, KeyEvent::from(KeyCode::Char('q'))),
+            Some(KeyAction::Quit)
+        );
+    }
+
+    #[test]
+    fn presets_have_no_conflicts() {
+        for name in ["default", "vim", "emacs"] {
+            assert_eq!(
+                Keymap::preset(name).unwrap().conflicts(),
+                Vec::<String>::new()
+            );
+        }
+    }
+
+    #[test]
+    fn parse_keymap_file() {
+        let keymap = Keymap::parse(
+            r#"
+preset = "vim"
+[counter]
+increment = ["+", "right"]
+reset = []
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            keymap.action(Context::Counter, KeyEvent::from(KeyCode::Char('+'))),
+            Some(KeyAction::Increment)
+        );
+        assert_eq!(
+            keymap.action(Context::Counter, KeyEvent::from(KeyCode::Char('l'))),
+            None
+        );
+        assert_eq!(keymap.key(Context::Counter, KeyAction::Reset), None);
+
+        let error = Keymap::parse("[menu]\nreset = \"r\"\n").unwrap_err();
+        assert_eq!(error.to_string(), "2: `reset`: cannot be bound in [menu]");
+        let error = Keymap::parse("[menu]\nup = \"hyper-k\"\n").unwrap_err();
+        assert_eq!(error.to_string(), "2: `up`: unknown key `hyper-k`");
+    }
+
+    #[test]
+    fn reports_conflicts() {
+        let keymap = Keymap::parse("[menu]\nopen = [\"enter\"]\nback = [\"u\"]\n").unwrap();
+        assert_eq!(
+            keymap.conflicts(),
+            [
+                "`Enter` is bound to both `activate` and `open` in [menu]",
+                "`u` is bound to `back` in [menu], which hides `undo` in [global]",
+            ]
+        );
+    }
+}
@@ -0,0 +1,68 @@
+//! A menu for the terminal.
+//!
+//! The `testo` binary shows a menu from a TOML file, or picks one of a list
+//! of items. Its parts can be used on their own: [`widget::MenuWidget`] draws
+//! a [`widget::MenuState`] in any ratatui app, and [`App`] is the whole app,
+//! which [`run`] drives until it quits. `examples/embed.rs` embeds the menu
+//! in another app.
+
+pub mod app;
+pub mod counter;
+pub mod events;
+pub mod filter;
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod fuzz;
+pub mod history;
+pub mod job;
+pub mod keymap;
+pub mod menu;
+pub mod script;
+#[cfg(test)]
+mod snapshot;
+pub mod state;
+pub mod terminal;
+pub mod theme;
+mod toml;
+pub mod widget;
+
+use std::io;
+
+use ratatui::Viewport;
+
+pub use crate::{
+    app::{App, Callback, Selection},
+    toml::ConfigError,
+};
+
+/// Runs `app` on stdout until it quits. The terminal is restored afterwards,
+/// also when the app panics. An inline viewport is cleared, so that only
+/// what is printed next stays in the scrollback.
+pub fn run(app: &mut App, viewport: Viewport) -> io::Result<()> {
+    terminal::install_panic_hook();
+    let inline = matches!(viewport, Viewport::Inline(_));
+    let mut terminal = terminal::init(viewport)?;
+    let app_result = app.run(&mut terminal);
+    let cleared = if inline {
+        terminal::clear_inline(&mut terminal, None)
+    } else {
+        Ok(())
+    };
+    app_result.and(cleared).and(terminal::restore())
+}
+
+/// Like [`run`], but draws on `/dev/tty` to keep stdout free for the result.
+/// An inline viewport is replaced by [`App::summary`].
+pub fn run_on_tty(app: &mut App, viewport: Viewport) -> io::Result<()> {
+    terminal::install_panic_hook();
+    let inline = matches!(viewport, Viewport::Inline(_));
+    let mut terminal = terminal::init_tty(viewport)?;
+    let app_result = app.run(&mut terminal);
+    let cleared = if inline {
+        terminal::clear_inline(&mut terminal, app.summary().as_deref())
+    } else {
+        Ok(())
+    };
+    app_result
+        .and(cleared)
+        .and(terminal::restore_tty(&mut terminal))
+}
@@ -1,172 +1,169 @@
-use std::io;
+mod cli;
+
+use std::{
+    env, fmt,
+    io::{self, IsTerminal},
+    path::Path,
+    process::ExitCode,
+    time::Duration,
+};
 
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
-use ratatui::{
-    DefaultTerminal, Frame,
-    buffer::Buffer,
-    layout::Rect,
-    style::Stylize,
-    symbols::border,
-    text::{Line, Text},
-    widgets::{Block, Paragraph, Widget},
+use testo::{
+    App,
+    events::Events,
+    history::History,
+    keymap::Keymap,
+    menu::Menu,
+    script::{Recorder, Script},
+    state,
+    theme::{self, Theme},
 };
 
-fn main() -> io::Result<()> {
-    let mut terminal = ratatui::init();
-    let app_result = App::default().run(&mut terminal);
-    ratatui::restore();
-    app_result
-}
+use crate::cli::Cli;
 
-#[derive(Debug)]
-pub struct App {
-    exit: bool,
-    menu_items: Vec<&'static str>,
-    active_menu_item: usize,
-}
+/// The exit code when the user quits the picker without choosing anything.
+const EXIT_CANCELLED: u8 = 130;
 
-impl Default for App {
-    fn default() -> Self {
-        Self {
-            exit: Default::default(),
-            menu_items: vec!["One", "Two", "Three"],
-            active_menu_item: 0,
-        }
-    }
+/// The exit code for invalid command-line arguments.
+const EXIT_USAGE: u8 = 2;
+
+fn main() -> ExitCode {
+    // Errors are printed for people, not with the `Debug` form that
+    // returning them from `main` would use.
+    run().unwrap_or_else(|error| {
+        eprintln!("error: {error}");
+        ExitCode::FAILURE
+    })
 }
 
-impl App {
-    pub fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
-        while !self.exit {
-            terminal.draw(|frame| self.draw(frame))?;
-            self.handle_events()?;
+fn run() -> io::Result<ExitCode> {
+    let cli = match Cli::parse(env::args().skip(1)) {
+        Ok(cli::Command::Run(cli)) => *cli,
+        Ok(cli::Command::Help) => {
+            print!("{}", cli::USAGE);
+            return Ok(ExitCode::SUCCESS);
+        }
+        Ok(cli::Command::Version) => {
+            println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+            return Ok(ExitCode::SUCCESS);
         }
-        Ok(())
+        Err(error) => return Ok(usage_error(error)),
+    };
+    // Theme names are told apart from theme files by their lack of slashes
+    // and extensions.
+    let theme = match cli.theme.as_deref() {
+        Some(name) if !name.contains(['/', '.']) => match Theme::preset(name) {
+            Some(theme) => theme,
+            None => {
+                return Ok(usage_error(format!(
+                    "unknown theme `{name}`, expected one of {} or a theme file",
+                    Theme::PRESETS.join(", ")
+                )));
+            }
+        },
+        path => Theme::load(path.map(Path::new))?,
+    };
+    let keymap = Keymap::load(cli.keymap.as_deref())?;
+    let conflicts = keymap.conflicts();
+    if !conflicts.is_empty() {
+        for conflict in conflicts {
+            eprintln!("error: {conflict}");
+        }
+        eprintln!("\nFix the key bindings in the keymap file.");
+        return Ok(ExitCode::from(EXIT_USAGE));
     }
 
-    pub fn draw(&self, frame: &mut Frame) {
-        frame.render_widget(self, frame.area());
+    // Set up the app before touching the terminal so that errors in the
+    // arguments or the menu file are printed to a normal screen.
+    if cli.menu.is_some() && !cli.items.is_empty() {
+        return Ok(usage_error("--menu cannot be used with items to pick from"));
     }
-
-    fn handle_events(&mut self) -> io::Result<()> {
-        match event::read()? {
-            // it's important to check that the event is a key press event as
-            // crossterm also emits key release and repeat events on Windows.
-            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
-                self.handle_key_event(key_event)
-            }
-            _ => {}
+    // An explicit menu is shown even when stdin is redirected.
+    let picking = !cli.items.is_empty() || (cli.menu.is_none() && !io::stdin().is_terminal());
+    let mut app = if picking {
+        let items = if cli.items.is_empty() {
+            io::stdin().lines().collect::<io::Result<_>>()?
+        } else {
+            cli.items
         };
-        Ok(())
-    }
-    fn handle_key_event(&mut self, key_event: KeyEvent) {
-        match key_event.code {
-            KeyCode::Char('q') => self.exit(),
-            KeyCode::Up | KeyCode::Char('j') => self.menu_up(),
-            KeyCode::Down | KeyCode::Char('k') => self.menu_down(),
-            _ => {}
+        if items.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no items to choose from",
+            ));
         }
-    }
-
-    fn menu_up(&mut self) {
-        if self.active_menu_item == 0 {
-            self.active_menu_item = self.menu_items.len() - 1;
+        if cli.multi {
+            App::multi_picker(items)
         } else {
-            self.active_menu_item -= 1;
+            App::picker(items)
         }
+    } else if cli.multi {
+        return Ok(usage_error("--multi needs items to pick from"));
+    } else {
+        App::new(Menu::load(cli.menu.as_deref())?)
+    };
+    let state_path = state::default_path().filter(|_| !picking);
+    if let Some(path) = &state_path {
+        app.counters = state::load(path)?;
     }
-
-    fn menu_down(&mut self) {
-        if self.active_menu_item == (self.menu_items.len() - 1) {
-            self.active_menu_item = 0;
-        } else {
-            self.active_menu_item += 1;
+    app.keymap = keymap;
+    if let Some(tick_rate) = cli.tick_rate {
+        app.events = Events::new(Duration::from_millis(tick_rate));
+    }
+    app.theme = if theme::no_color() {
+        theme.without_colors()
+    } else {
+        theme
+    };
+    if let Some(title) = cli.title {
+        app.menu.title = title;
+    }
+    if let Some(limit) = cli.history {
+        app.history = History::new(limit);
+    }
+    if let Some(select) = cli.select {
+        let len = app.menu.items().len();
+        if select >= len {
+            return Ok(usage_error(format!(
+                "--select {select} is out of range for {len} items"
+            )));
         }
+        app.menu.select(select);
     }
-
-    fn exit(&mut self) {
-        self.exit = true;
+    if let Some(path) = &cli.replay {
+        app.replay(Script::load(path)?.events(), cli.replay_speed.unwrap_or(1));
     }
-}
-
-impl Widget for &App {
-    fn render(self, area: Rect, buf: &mut Buffer)
-    where
-        Self: Sized,
-    {
-        let title = Line::from(" Test Application Main Menu ".bold());
-
-        let instructions = Line::from(vec![" Quit ".into(), "<Q> ".blue().bold()]);
-
-        let block = Block::bordered()
-            .title(title.centered())
-            .title_bottom(instructions.centered())
-            .border_set(border::THICK);
-
-        let menu_lines: Vec<Line> = self
-            .menu_items
-            .iter()
-            .enumerate()
-            .map(|(i, menu_item)| {
-                let mut line = Line::from(*menu_item);
-                if self.active_menu_item == i {
-                    line = line.bold().red();
-                }
-                line
-            })
-            .collect();
-
-        let menu_text = Text::from(menu_lines);
-
-        Paragraph::new(menu_text)
-            .centered()
-            .block(block)
-            .render(area, buf);
+    if let Some(path) = &cli.record {
+        app.record(Recorder::create(path)?);
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use ratatui::style::Style;
 
-    fn render() {
-        let app = App::default();
-        let mut buf = Buffer::empty(Rect::new(0, 0, 50, 4));
-
-        app.render(buf.area, &mut buf);
-
-        let mut expected = Buffer::with_lines(vec![
-            "┏━━━━━━━━━━━━━ Counter App Tutorial ━━━━━━━━━━━━━┓",
-            "┃                    Value: 0                    ┃",
-            "┃                                                ┃",
-            "┗━ Decrement <Left> Increment <Right> Quit <Q> ━━┛",
-        ]);
-        let title_style = Style::new().bold();
-        let counter_style = Style::new().yellow();
-        let key_style = Style::new().blue().bold();
-        expected.set_style(Rect::new(14, 0, 22, 1), title_style);
-        expected.set_style(Rect::new(28, 1, 1, 1), counter_style);
-        expected.set_style(Rect::new(13, 3, 6, 1), key_style);
-        expected.set_style(Rect::new(30, 3, 7, 1), key_style);
-        expected.set_style(Rect::new(43, 3, 4, 1), key_style);
-
-        assert_eq!(buf, expected);
+    // Pickers draw on /dev/tty to keep stdout clean for the choice.
+    let result = if picking {
+        testo::run_on_tty(&mut app, cli.viewport)
+    } else {
+        testo::run(&mut app, cli.viewport)
+    };
+    // Save even when the terminal is gone, as it is after a SIGHUP.
+    if let Some(path) = &state_path {
+        state::save(path, &app.counters)?;
     }
-    #[test]
-    fn handle_key_event() -> io::Result<()> {
-        let mut app = App::default();
-
-        app.handle_key_event(KeyCode::Down.into());
-        assert_eq!(app.active_menu_item, 1);
-
-        app.handle_key_event(KeyCode::Up.into());
-        assert_eq!(app.active_menu_item, 0);
-
-        let mut app = App::default();
-        app.handle_key_event(KeyCode::Char('q').into());
-        assert!(app.exit);
+    if let Some(signal) = app.signal() {
+        // The conventional exit code for being stopped by a signal.
+        return Ok(ExitCode::from(128 + signal as u8));
+    }
+    result?;
 
-        Ok(())
+    for selection in app.selections() {
+        println!("{}", cli.output.format(selection));
+    }
+    if picking && app.selections().is_empty() {
+        Ok(ExitCode::from(EXIT_CANCELLED))
+    } else {
+        Ok(ExitCode::SUCCESS)
     }
 }
+
+fn usage_error(error: impl fmt::Display) -> ExitCode {
+    eprintln!("error: {error}\n\nFor more information, try '--help'.");
+    ExitCode::from(EXIT_USAGE)
+}
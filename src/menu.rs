@@ -0,0 +1,672 @@
+//! Menu definitions and loading them from a TOML file.
+//!
+//! A menu file looks like this:
+//!
+//! ```toml
+//! title = "Main"
+//!
+//! [[item]]
+//! id = "greet"
+//! label = "Say hello"
+//! description = "Prints a greeting and exits"
+//! print = "hello"
+//!
+//! [[item]]
+//! label = "Settings"
+//!
+//! [[item.item]]
+//! label = "Dark mode"
+//! set = "theme=dark"
+//!
+//! [[item.item]]
+//! label = "Verbose"
+//! checkbox = true
+//! print = "--verbose"
+//!
+//! [[item.item]]
+//! label = "Small"
+//! radio = "size"
+//!
+//! [[item.item]]
+//! label = "Large"
+//! radio = "size"
+//! checked = true
+//!
+//! [[item]]
+//! separator = true
+//!
+//! [[item]]
+//! header = "Tools"
+//!
+//! [[item]]
+//! label = "Counter"
+//!
+//! [item.counter]
+//! step = 5
+//! min = 0
+//! max = 100
+//!
+//! [[item]]
+//! label = "Sync"
+//! command = "git pull"
+//! disabled = "needs a network"
+//! ```
+//!
+//! Each item may have at most one action key (`command`, `interactive`,
+//! `print`, `set`, `callback` or a `[item.counter]` table), and items with
+//! children (`[[item.item]]`) may not have one. Separators and headers only
+//! group the items around them and cannot be chosen. Neither can disabled
+//! items, which are shown dimmed with the reason given, if any.
+//!
+//! `set = "theme=NAME"` switches to one of the built-in themes. Other
+//! settings have no effect of their own and are only seen by callbacks.
+//!
+//! Checkboxes (`checkbox = true`) and radio buttons (`radio = "group"`) are
+//! toggled instead of run, and only checking one radio button unchecks the
+//! others in its group and menu. They may have a `print` action, whose value
+//! stands for them among the checked items when one is chosen.
+
+use std::{
+    env, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    counter::CounterConfig,
+    theme::Theme,
+    toml::{self, ConfigError, Entry, Table, Value},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Run a shell command with `sh -c`.
+    Command(String),
+    /// Run a shell command that needs the whole terminal, like an editor or
+    /// a pager, and come back to the menu when it exits.
+    Interactive(String),
+    /// Print the value to stdout and exit.
+    Print(String),
+    /// Set an application setting. `theme` switches to a built-in theme,
+    /// and the others are only seen by callbacks.
+    Set { key: String, value: String },
+    /// Call a callback registered by name on the `App`.
+    Callback(String),
+    /// Open the counter screen.
+    Counter(CounterConfig),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ItemKind {
+    /// An item that can be chosen.
+    #[default]
+    Item,
+    /// A line between groups of items.
+    Separator,
+    /// The title of the items below it.
+    Header,
+}
+
+/// How an item is toggled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Toggle {
+    Checkbox,
+    /// A radio button, of which only one in the group can be checked.
+    Radio {
+        group: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MenuItem {
+    pub id: String,
+    pub label: String,
+    pub description: Option<String>,
+    pub kind: ItemKind,
+    /// Set when the item cannot be chosen, to the reason why, which may be
+    /// empty.
+    pub disabled: Option<String>,
+    /// Set for checkboxes and radio buttons.
+    pub toggle: Option<Toggle>,
+    pub checked: bool,
+    pub action: Option<Action>,
+    pub children: Vec<MenuItem>,
+}
+
+impl MenuItem {
+    pub fn new(label: impl Into<String>) -> Self {
+        let label = label.into();
+        Self {
+            id: slug(&label),
+            label,
+            description: None,
+            kind: ItemKind::Item,
+            disabled: None,
+            toggle: None,
+            checked: false,
+            action: None,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn separator() -> Self {
+        Self {
+            kind: ItemKind::Separator,
+            ..Self::new("")
+        }
+    }
+
+    pub fn header(label: impl Into<String>) -> Self {
+        Self {
+            kind: ItemKind::Header,
+            ..Self::new(label)
+        }
+    }
+
+    /// Whether the item can be selected and chosen: it is not a separator,
+    /// a header or disabled.
+    pub fn is_selectable(&self) -> bool {
+        self.kind == ItemKind::Item && self.disabled.is_none()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Menu {
+    pub title: String,
+    pub items: Vec<MenuItem>,
+}
+
+impl Default for Menu {
+    fn default() -> Self {
+        Self {
+            title: "Main".into(),
+            items: vec![
+                MenuItem::new("One"),
+                MenuItem::new("Two"),
+                MenuItem::new("Three"),
+                MenuItem {
+                    action: Some(Action::Counter(CounterConfig::default())),
+                    ..MenuItem::new("Counters")
+                },
+            ],
+        }
+    }
+}
+
+impl Menu {
+    /// Loads the menu from `path`, or from the user's config directory when
+    /// no path is given. Falls back to the built-in menu when there is no
+    /// menu file in the config directory.
+    pub fn load(path: Option<&Path>) -> io::Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match default_path().filter(|path| path.exists()) {
+                Some(path) => path,
+                None => return Ok(Self::default()),
+            },
+        };
+        let source = toml::read(&path)?;
+        Self::parse(&source).map_err(|error| error.in_file(&path).into())
+    }
+
+    pub fn parse(source: &str) -> Result<Self, ConfigError> {
+        let root = toml::parse(source)?;
+        let mut title = Self::default().title;
+        let mut items = Vec::new();
+        for entry in &root.entries {
+            match entry.key.as_str() {
+                "title" => title = entry.string()?,
+                "item" => items = parse_items(entry)?,
+                _ => return Err(entry.unknown_key()),
+            }
+        }
+        if items.is_empty() {
+            return Err(ConfigError {
+                path: None,
+                line: 1,
+                key: Some("item".into()),
+                message: "the menu needs at least one `[[item]]`".into(),
+            });
+        }
+        Ok(Self { title, items })
+    }
+}
+
+/// `$XDG_CONFIG_HOME/testo/menu.toml`, or `~/.config/testo/menu.toml`.
+pub fn default_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("menu.toml"))
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join(env!("CARGO_PKG_NAME")))
+}
+
+fn parse_items(entry: &Entry) -> Result<Vec<MenuItem>, ConfigError> {
+    let Value::TableArray(tables) = &entry.value else {
+        return Err(entry.invalid_type("an array of tables (`[[item]]`)"));
+    };
+    let mut items: Vec<MenuItem> = Vec::with_capacity(tables.len());
+    for table in tables {
+        let item = parse_item(table)?;
+        if let (Some(Toggle::Radio { group }), true) = (&item.toggle, item.checked)
+            && items
+                .iter()
+                .any(|other| other.checked && other.toggle == item.toggle)
+        {
+            let entry = table.get("checked").expect("checked is set");
+            return Err(entry.error(&format!(
+                "only one radio button in the group `{group}` can be checked"
+            )));
+        }
+        // Separators and headers are not referred to, so their ids may repeat.
+        if item.kind == ItemKind::Item && items.iter().any(|other| other.id == item.id) {
+            return Err(ConfigError {
+                path: None,
+                line: table.line,
+                key: Some("id".into()),
+                message: format!("duplicate id `{}` in the same menu", item.id),
+            });
+        }
+        items.push(item);
+    }
+    Ok(items)
+}
+
+fn parse_item(table: &Table) -> Result<MenuItem, ConfigError> {
+    let mut id = None;
+    let mut label = None;
+    let mut description = None;
+    let mut kind: Option<(&Entry, ItemKind)> = None;
+    let mut disabled = None;
+    let mut toggle: Option<(&Entry, Toggle)> = None;
+    let mut checked: Option<(&Entry, bool)> = None;
+    let mut action: Option<(&Entry, Action)> = None;
+    let mut children = Vec::new();
+
+    for entry in &table.entries {
+        let parsed = match entry.key.as_str() {
+            "id" => {
+                id = Some(entry.string()?);
+                None
+            }
+            "label" => {
+                label = Some(entry.string()?);
+                None
+            }
+            "description" => {
+                description = Some(entry.string()?);
+                None
+            }
+            "separator" => {
+                if entry.boolean()? {
+                    kind = Some((entry, ItemKind::Separator));
+                }
+                None
+            }
+            "header" => {
+                label = Some(entry.string()?);
+                kind = Some((entry, ItemKind::Header));
+                None
+            }
+            "disabled" => {
+                disabled = match &entry.value {
+                    Value::Boolean(disabled) => disabled.then(String::new),
+                    Value::String(reason) => Some(reason.clone()),
+                    _ => return Err(entry.invalid_type("a boolean or a reason")),
+                };
+                None
+            }
+            "checkbox" | "radio" => {
+                if let Some((previous, _)) = &toggle {
+                    return Err(entry.error(&format!(
+                        "an item can only be one kind of toggle, but `{}` is already set on line {}",
+                        previous.key, previous.line
+                    )));
+                }
+                if entry.key == "radio" {
+                    let group = entry.string()?;
+                    toggle = Some((entry, Toggle::Radio { group }));
+                } else if entry.boolean()? {
+                    toggle = Some((entry, Toggle::Checkbox));
+                }
+                None
+            }
+            "checked" => {
+                checked = Some((entry, entry.boolean()?));
+                None
+            }
+            "item" => {
+                children = parse_items(entry)?;
+                None
+            }
+            "command" => Some(Action::Command(entry.string()?)),
+            "interactive" => Some(Action::Interactive(entry.string()?)),
+            "print" => Some(Action::Print(entry.string()?)),
+            "callback" => Some(Action::Callback(entry.string()?)),
+            "counter" => Some(Action::Counter(parse_counter(entry)?)),
+            "set" => {
+                let setting = entry.string()?;
+                let Some((key, value)) = setting.split_once('=') else {
+                    return Err(entry.error("expected `key=value`"));
+                };
+                if key.trim() == "theme" && Theme::preset(value.trim()).is_none() {
+                    return Err(entry.error(&format!(
+                        "unknown theme `{}`, expected one of {}",
+                        value.trim(),
+                        Theme::PRESETS.join(", ")
+                    )));
+                }
+                Some(Action::Set {
+                    key: key.trim().into(),
+                    value: value.trim().into(),
+                })
+            }
+            _ => return Err(entry.unknown_key()),
+        };
+        if let Some(parsed) = parsed {
+            if let Some((previous, _)) = &action {
+                return Err(entry.error(&format!(
+                    "an item can only have one action, but `{}` is already set on line {}",
+                    previous.key, previous.line
+                )));
+            }
+            action = Some((entry, parsed));
+        }
+    }
+
+    if let Some((entry, kind)) = kind {
+        let other = table
+            .entries
+            .iter()
+            .find(|other| other.key != "id" && other.key != entry.key);
+        if let Some(other) = other {
+            return Err(other.error(&format!(
+                "not allowed with `{}` on line {}, which only takes an `id`",
+                entry.key, entry.line
+            )));
+        }
+        return Ok(MenuItem {
+            kind,
+            id: id.unwrap_or_default(),
+            ..MenuItem::new(label.unwrap_or_default())
+        });
+    }
+    let Some(label) = label else {
+        return Err(ConfigError {
+            path: None,
+            line: table.line,
+            key: Some("label".into()),
+            message: "missing required key".into(),
+        });
+    };
+    if let (Some((entry, _)), false) = (&action, children.is_empty()) {
+        return Err(entry.error("items with a submenu cannot have an action"));
+    }
+    if let (Some((entry, _)), None) = (&checked, &toggle) {
+        return Err(entry.error("only checkboxes and radio buttons can be checked"));
+    }
+    if let Some((entry, _)) = &toggle {
+        if !children.is_empty() {
+            return Err(entry.error("items with a submenu cannot be toggled"));
+        }
+        if let Some((action, value)) = &action
+            && !matches!(value, Action::Print(_))
+        {
+            return Err(action.error(&format!(
+                "`{}` items can only have a `print` action",
+                entry.key
+            )));
+        }
+    }
+    Ok(MenuItem {
+        id: id.unwrap_or_else(|| slug(&label)),
+        label,
+        description,
+        kind: ItemKind::Item,
+        disabled,
+        toggle: toggle.map(|(_, toggle)| toggle),
+        checked: checked.is_some_and(|(_, checked)| checked),
+        action: action.map(|(_, action)| action),
+        children,
+    })
+}
+
+fn parse_counter(entry: &Entry) -> Result<CounterConfig, ConfigError> {
+    let Value::Table(table) = &entry.value else {
+        return Err(entry.invalid_type("a table (`[item.counter]`)"));
+    };
+    let mut config = CounterConfig::default();
+    for entry in &table.entries {
+        match entry.key.as_str() {
+            "step" => {
+                config.step = entry.integer()?;
+                if config.step <= 0 {
+                    return Err(entry.error("the step must be positive"));
+                }
+            }
+            "min" => config.min = Some(entry.integer()?),
+            "max" => config.max = Some(entry.integer()?),
+            _ => return Err(entry.unknown_key()),
+        }
+    }
+    if let (Some(min), Some(max)) = (config.min, config.max)
+        && min > max
+    {
+        let entry = table.get("max").expect("max is set");
+        return Err(entry.error(&format!("must not be less than min ({min})")));
+    }
+    Ok(config)
+}
+
+/// Turns a label into an id: `"Say Hello!"` becomes `"say-hello"`.
+fn slug(label: &str) -> String {
+    label
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_menu() {
+        let menu = Menu::parse(
+            r#"
+title = "Main"
+
+[[item]]
+label = "Say Hello!"
+print = "hello"
+
+[[item]]
+id = "settings"
+label = "Settings"
+description = "Application settings"
+
+[[item.item]]
+label = "Dark mode"
+set = "theme = dark"
+
+[[item]]
+label = "Counter"
+[item.counter]
+step = 5
+min = -10
+
+[[item]]
+label = "Notes"
+interactive = "$EDITOR notes.txt"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(menu.title, "Main");
+        assert_eq!(menu.items[0].id, "say-hello");
+        assert_eq!(menu.items[0].action, Some(Action::Print("hello".into())));
+        assert_eq!(menu.items[1].children[0].id, "dark-mode");
+        assert_eq!(
+            menu.items[1].children[0].action,
+            Some(Action::Set {
+                key: "theme".into(),
+                value: "dark".into()
+            })
+        );
+        assert_eq!(
+            menu.items[2].action,
+            Some(Action::Counter(CounterConfig {
+                step: 5,
+                min: Some(-10),
+                max: None,
+            }))
+        );
+        assert_eq!(
+            menu.items[3].action,
+            Some(Action::Interactive("$EDITOR notes.txt".into()))
+        );
+    }
+
+    #[test]
+    fn parse_sections() {
+        let menu = Menu::parse(
+            r#"
+[[item]]
+header = "Files"
+
+[[item]]
+label = "Open"
+disabled = true
+
+[[item]]
+separator = true
+
+[[item]]
+separator = true
+
+[[item]]
+label = "Sync"
+disabled = "needs a network"
+
+[[item]]
+label = "Quit"
+disabled = false
+"#,
+        )
+        .unwrap();
+
+        let items = &menu.items;
+        assert_eq!(items[0].kind, ItemKind::Header);
+        assert_eq!(items[0].label, "Files");
+        assert_eq!(items[1].disabled.as_deref(), Some(""));
+        assert_eq!(items[2].kind, ItemKind::Separator);
+        assert_eq!(items[3].kind, ItemKind::Separator);
+        assert_eq!(items[4].disabled.as_deref(), Some("needs a network"));
+        assert_eq!(items[5].disabled, None);
+        let selectable: Vec<_> = items.iter().map(MenuItem::is_selectable).collect();
+        assert_eq!(selectable, [false, false, false, false, false, true]);
+    }
+
+    #[test]
+    fn parse_toggles() {
+        let menu = Menu::parse(
+            r#"
+[[item]]
+label = "Verbose"
+checkbox = true
+checked = true
+print = "-v"
+
+[[item]]
+label = "Small"
+radio = "size"
+checked = true
+
+[[item]]
+label = "Large"
+radio = "size"
+"#,
+        )
+        .unwrap();
+
+        let items = &menu.items;
+        assert_eq!(items[0].toggle, Some(Toggle::Checkbox));
+        assert!(items[0].checked);
+        assert_eq!(items[0].action, Some(Action::Print("-v".into())));
+        let size = Some(Toggle::Radio {
+            group: "size".into(),
+        });
+        assert_eq!(items[1].toggle, size);
+        assert!(items[1].checked);
+        assert_eq!(items[2].toggle, size);
+        assert!(!items[2].checked);
+    }
+
+    #[test]
+    fn validation_errors_name_line_and_key() {
+        let error = Menu::parse("[[item]]\nlabel = 3\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "2: `label`: expected a string, found an integer"
+        );
+
+        let error = Menu::parse("[[item]]\nid = \"x\"\n").unwrap_err();
+        assert_eq!(error.to_string(), "1: `label`: missing required key");
+
+        let error =
+            Menu::parse("[[item]]\nlabel = \"x\"\nprint = \"a\"\ncommand = \"b\"\n").unwrap_err();
+        assert_eq!(error.line, 4);
+        assert_eq!(error.key.as_deref(), Some("command"));
+
+        let error =
+            Menu::parse("[[item]]\nlabel = \"x\"\n[item.counter]\nmin = 3\nmax = 1\n").unwrap_err();
+        assert_eq!(error.to_string(), "5: `max`: must not be less than min (3)");
+
+        let error = Menu::parse("[[item]]\nlabel = \"x\"\nset = \"theme=pink\"\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "3: `set`: unknown theme `pink`, expected one of default, light, dark, \
+             high-contrast, monochrome"
+        );
+
+        let error = Menu::parse("[[item]]\nlabel = \"x\"\ncolour = \"red\"\n").unwrap_err();
+        assert_eq!(error.to_string(), "3: `colour`: unknown key");
+
+        let error = Menu::parse("[[item]]\nseparator = true\nprint = \"a\"\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "3: `print`: not allowed with `separator` on line 2, which only takes an `id`"
+        );
+
+        let error = Menu::parse("[[item]]\nlabel = \"x\"\ndisabled = 1\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "3: `disabled`: expected a boolean or a reason, found an integer"
+        );
+
+        let error = Menu::parse("[[item]]\nlabel = \"x\"\nchecked = true\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "3: `checked`: only checkboxes and radio buttons can be checked"
+        );
+
+        let error = Menu::parse("[[item]]\nlabel = \"x\"\ncheckbox = true\ncommand = \"ls\"\n")
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "4: `command`: `checkbox` items can only have a `print` action"
+        );
+
+        let radio = "[[item]]\nlabel = \"x\"\nradio = \"g\"\nchecked = true\n";
+        let error = Menu::parse(&radio.repeat(2)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "8: `checked`: only one radio button in the group `g` can be checked"
+        );
+    }
+}
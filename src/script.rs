@@ -0,0 +1,660 @@
+//! Scripts that drive the app without a terminal, for end-to-end tests,
+//! and recordings of what the app received, for replaying it.
+//!
+//! A script is a plain-text file with one command per line. Blank lines and
+//! lines starting with `#` are ignored:
+//!
+//! ```text
+//! # Set the app up. Menu files are relative to the script.
+//! menu fruit.toml
+//! keymap vim
+//! resize 40 10
+//!
+//! # Feed it events. Keys are written as in keymap files.
+//! press down down enter
+//! type ban
+//! paste banana
+//! paste "two\nlines"
+//! mouse down-left 12 3
+//! tick 3
+//! signal 15
+//!
+//! # Check where it got to.
+//! expect selected banana
+//! expect title Select › Fruit
+//! expect contains Quit <q>
+//! expect counter 2
+//! expect status no command has run yet
+//! expect choice banana
+//! expect quit
+//! expect screen
+//! | ┏━━━━━━━━━━ Select ━━━━━━━━━━┓
+//! | ┃           apple            ┃
+//! ```
+//!
+//! Pasted text in quotes is unescaped like a Rust string. Mouse events are
+//! `down`, `up` or `drag` with `-left`, `-right` or `-middle`, `moved`, or
+//! `scroll-up`, `-down`, `-left` or `-right`, followed by the column and the
+//! row. `expect status` without a message expects no status. `expect choice`
+//! takes the labels of the chosen items, separated by `, ` when several
+//! were checked. `expect screen`
+//! is followed by every row of the screen, each starting with `|`. Trailing
+//! spaces are ignored there.
+//!
+//! The screen is 80 by 24 cells until the script resizes it, and the app is
+//! drawn after every event, as it is in a terminal.
+//!
+//! A [`Recorder`] writes the events the app receives as a script, each line
+//! starting with the milliseconds since the recording started. The first
+//! event is the size of the terminal the app ran in:
+//!
+//! ```text
+//! 0 resize 80 24
+//! 250 tick
+//! 731 press down
+//! ```
+//!
+//! Adding checks to a recording turns it into a test.
+
+use std::{
+    fs::{self, File},
+    io::{self, LineWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use ratatui::{Terminal, backend::TestBackend, buffer::Buffer};
+
+use crate::{
+    App, ConfigError,
+    events::{AppEvent, SIGNALS},
+    keymap::{Key, Keymap},
+    menu::Menu,
+    widget::MenuState,
+};
+
+/// The names of mouse events in scripts.
+const MOUSE_EVENTS: [(&str, MouseEventKind); 14] = [
+    ("down-left", MouseEventKind::Down(MouseButton::Left)),
+    ("down-right", MouseEventKind::Down(MouseButton::Right)),
+    ("down-middle", MouseEventKind::Down(MouseButton::Middle)),
+    ("up-left", MouseEventKind::Up(MouseButton::Left)),
+    ("up-right", MouseEventKind::Up(MouseButton::Right)),
+    ("up-middle", MouseEventKind::Up(MouseButton::Middle)),
+    ("drag-left", MouseEventKind::Drag(MouseButton::Left)),
+    ("drag-right", MouseEventKind::Drag(MouseButton::Right)),
+    ("drag-middle", MouseEventKind::Drag(MouseButton::Middle)),
+    ("moved", MouseEventKind::Moved),
+    ("scroll-up", MouseEventKind::ScrollUp),
+    ("scroll-down", MouseEventKind::ScrollDown),
+    ("scroll-left", MouseEventKind::ScrollLeft),
+    ("scroll-right", MouseEventKind::ScrollRight),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Menu(PathBuf),
+    Keymap(String),
+    Events(Vec<AppEvent>),
+    Expect(Expectation),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expectation {
+    Selected(String),
+    Title(String),
+    Contains(String),
+    Screen(Vec<String>),
+    Counter(i64),
+    Status(Option<String>),
+    Choice(String),
+    Quit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Line {
+    number: usize,
+    /// When the line's events happened, for recordings.
+    time: Option<Duration>,
+    step: Step,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Script {
+    lines: Vec<Line>,
+}
+
+impl Script {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let source = fs::read_to_string(path).map_err(|error| name_file(path, error))?;
+        let mut script = Self::parse(&source).map_err(|error| error.in_file(path))?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for line in &mut script.lines {
+            if let Step::Menu(menu) = &mut line.step {
+                *menu = dir.join(&*menu);
+            }
+        }
+        Ok(script)
+    }
+
+    pub fn parse(source: &str) -> Result<Self, ConfigError> {
+        let mut lines: Vec<Line> = Vec::new();
+        for (index, raw) in source.lines().enumerate() {
+            let number = index + 1;
+            let trimmed = raw.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let error = |key: &str, message: String| ConfigError {
+                path: None,
+                line: number,
+                key: Some(key.into()),
+                message,
+            };
+            if let Some(row) = trimmed.strip_prefix('|') {
+                let row = row.strip_prefix(' ').unwrap_or(row);
+                match lines.last_mut().map(|line| &mut line.step) {
+                    Some(Step::Expect(Expectation::Screen(rows))) => {
+                        rows.push(row.trim_end().into());
+                        continue;
+                    }
+                    _ => return Err(error("|", "screen row outside `expect screen`".into())),
+                }
+            }
+            let (mut command, mut rest) = split_word(trimmed);
+            let mut time = None;
+            if let Ok(millis) = command.parse() {
+                time = Some(Duration::from_millis(millis));
+                (command, rest) = split_word(rest);
+            }
+            let step = match command {
+                "menu" if !rest.is_empty() => Step::Menu(rest.into()),
+                "keymap" if Keymap::preset(rest).is_some() => Step::Keymap(rest.into()),
+                "keymap" => {
+                    return Err(error(
+                        command,
+                        "expected `default`, `vim` or `emacs`".into(),
+                    ));
+                }
+                "expect" => Step::Expect(
+                    parse_expectation(rest)
+                        .ok_or_else(|| error(command, format!("unknown check `{trimmed}`")))?,
+                ),
+                _ => Step::Events(parse_events(command, rest).map_err(|e| error(command, e))?),
+            };
+            lines.push(Line { number, time, step });
+        }
+        Ok(Self { lines })
+    }
+
+    /// The events in the script with the times they happened at, for
+    /// replaying it. Events without a time happen with the ones before.
+    pub fn events(&self) -> Vec<(Duration, AppEvent)> {
+        let mut time = Duration::ZERO;
+        let mut events = Vec::new();
+        for line in &self.lines {
+            time = line.time.unwrap_or(time);
+            if let Step::Events(line_events) = &line.step {
+                events.extend(line_events.iter().map(|event| (time, event.clone())));
+            }
+        }
+        events
+    }
+
+    /// Runs the script on `app`. Fails at the first check that does not
+    /// hold, naming its line.
+    pub fn run(&self, app: &mut App) -> Result<(), String> {
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).map_err(|e| e.to_string())?;
+        let mut screen = draw(&mut terminal, app)?;
+        for line in &self.lines {
+            let at_line = |error: String| format!("line {}: {error}", line.number);
+            match &line.step {
+                Step::Menu(path) => {
+                    let menu = Menu::load(Some(path)).map_err(|e| at_line(e.to_string()))?;
+                    app.menu = MenuState::new(menu);
+                }
+                Step::Keymap(name) => app.keymap = Keymap::preset(name).unwrap_or_default(),
+                Step::Events(events) => {
+                    for event in events {
+                        if let AppEvent::Terminal(Event::Resize(width, height)) = *event {
+                            terminal.backend_mut().resize(width, height);
+                        }
+                        app.handle_event(event.clone());
+                        draw(&mut terminal, app)?;
+                    }
+                }
+                Step::Expect(expectation) => {
+                    check(app, &screen, expectation).map_err(at_line)?;
+                    continue;
+                }
+            }
+            screen = draw(&mut terminal, app)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the events the app receives to a file, as a script with the time
+/// of each event. Messages from background jobs are left out, as the jobs
+/// run again when the recording is replayed.
+#[derive(Debug)]
+pub struct Recorder {
+    file: LineWriter<File>,
+    started: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let file = File::create(path).map_err(|error| name_file(path, error))?;
+        let mut recorder = Self {
+            file: LineWriter::new(file),
+            started: Instant::now(),
+        };
+        writeln!(
+            recorder.file,
+            "# Recorded by testo {}",
+            env!("CARGO_PKG_VERSION")
+        )?;
+        Ok(recorder)
+    }
+
+    pub fn record(&mut self, event: &AppEvent) -> io::Result<()> {
+        let Some(command) = format_event(event) else {
+            return Ok(());
+        };
+        // Written line by line, so that the recording survives a crash.
+        let millis = self.started.elapsed().as_millis();
+        writeln!(self.file, "{millis} {command}")
+    }
+}
+
+/// Names the file at `path` in an error reading or writing it.
+fn name_file(path: &Path, error: io::Error) -> io::Error {
+    io::Error::new(error.kind(), format!("{}: {error}", path.display()))
+}
+
+/// The event as a script command, or `None` for events that make no
+/// difference to the app.
+fn format_event(event: &AppEvent) -> Option<String> {
+    match event {
+        AppEvent::Terminal(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+            Some(format!("press {}", Key::from(*key).name()?))
+        }
+        AppEvent::Terminal(Event::Mouse(mouse)) => {
+            let (name, _) = MOUSE_EVENTS.iter().find(|(_, kind)| *kind == mouse.kind)?;
+            Some(format!("mouse {name} {} {}", mouse.column, mouse.row))
+        }
+        AppEvent::Terminal(Event::Paste(text)) => Some(format!("paste {text:?}")),
+        AppEvent::Terminal(Event::Resize(width, height)) => {
+            Some(format!("resize {width} {height}"))
+        }
+        AppEvent::Terminal(_) | AppEvent::Message(_) => None,
+        AppEvent::Tick => Some("tick".into()),
+        AppEvent::Signal(signal) => Some(format!("signal {signal}")),
+    }
+}
+
+fn parse_events(command: &str, rest: &str) -> Result<Vec<AppEvent>, String> {
+    let terminal = |event| AppEvent::Terminal(event);
+    let numbers = |text: &str, count: usize| {
+        let numbers: Vec<u16> = text
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .unwrap_or_default();
+        Some(numbers).filter(|numbers| numbers.len() == count)
+    };
+    let events = match command {
+        "resize" => match numbers(rest, 2).as_deref() {
+            Some(&[width, height]) if width > 0 && height > 0 => {
+                vec![terminal(Event::Resize(width, height))]
+            }
+            _ => return Err("expected a width and a height".into()),
+        },
+        "press" => {
+            let keys = rest
+                .split_whitespace()
+                .map(|key| Key::parse(key).map(|key| terminal(Event::Key(key.into()))))
+                .collect::<Result<Vec<_>, _>>()?;
+            if keys.is_empty() {
+                return Err("expected keys".into());
+            }
+            keys
+        }
+        "type" => rest
+            .chars()
+            .map(|c| terminal(Event::Key(KeyEvent::from(KeyCode::Char(c)))))
+            .collect(),
+        "paste" => {
+            let text = match rest.strip_prefix('"').and_then(|r| r.strip_suffix('"')) {
+                Some(quoted) => unescape(quoted)?,
+                None => rest.into(),
+            };
+            vec![terminal(Event::Paste(text))]
+        }
+        "mouse" => {
+            let (name, rest) = split_word(rest);
+            let Some(&(_, kind)) = MOUSE_EVENTS.iter().find(|(n, _)| *n == name) else {
+                return Err(format!("unknown mouse event `{name}`"));
+            };
+            let Some(&[column, row]) = numbers(rest, 2).as_deref() else {
+                return Err("expected a column and a row".into());
+            };
+            vec![terminal(Event::Mouse(MouseEvent {
+                kind,
+                column,
+                row,
+                modifiers: KeyModifiers::NONE,
+            }))]
+        }
+        "tick" if rest.is_empty() => vec![AppEvent::Tick],
+        "tick" => match rest.parse() {
+            Ok(count) => vec![AppEvent::Tick; count],
+            Err(_) => return Err("expected a number of ticks".into()),
+        },
+        "signal" => match rest.parse() {
+            Ok(signal) if SIGNALS.contains(&signal) => vec![AppEvent::Signal(signal)],
+            _ => {
+                let signals: Vec<_> = SIGNALS.iter().map(i32::to_string).collect();
+                return Err(format!("expected one of {}", signals.join(", ")));
+            }
+        },
+        _ => return Err(format!("unknown command `{command}`")),
+    };
+    Ok(events)
+}
+
+fn parse_expectation(text: &str) -> Option<Expectation> {
+    let (what, rest) = split_word(text);
+    let expectation = match what {
+        "selected" => Expectation::Selected(rest.into()),
+        "title" => Expectation::Title(rest.into()),
+        "contains" if !rest.is_empty() => Expectation::Contains(rest.into()),
+        "screen" if rest.is_empty() => Expectation::Screen(Vec::new()),
+        "counter" => Expectation::Counter(rest.parse().ok()?),
+        "status" if rest.is_empty() => Expectation::Status(None),
+        "status" => Expectation::Status(Some(rest.into())),
+        "choice" => Expectation::Choice(rest.into()),
+        "quit" if rest.is_empty() => Expectation::Quit,
+        _ => return None,
+    };
+    Some(expectation)
+}
+
+/// Undoes the escapes of Rust's `{:?}` for strings.
+fn unescape(quoted: &str) -> Result<String, String> {
+    let mut text = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        let unescaped = match chars.next() {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('0') => '\0',
+            Some(c @ ('\\' | '"' | '\'')) => c,
+            Some('u') => {
+                let code: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                code.strip_prefix('{')
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("invalid escape `\\u{code}}}`"))?
+            }
+            Some(c) => return Err(format!("invalid escape `\\{c}`")),
+            None => return Err("a quote ends in `\\`".into()),
+        };
+        text.push(unescaped);
+    }
+    Ok(text)
+}
+
+/// Draws `app` and returns what is on the screen.
+fn draw(terminal: &mut Terminal<TestBackend>, app: &mut App) -> Result<Buffer, String> {
+    let frame = terminal
+        .draw(|frame| app.draw(frame))
+        .map_err(|e| e.to_string())?;
+    Ok(frame.buffer.clone())
+}
+
+/// Splits off the first word of `text`.
+fn split_word(text: &str) -> (&str, &str) {
+    match text.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim_start()),
+        None => (text, ""),
+    }
+}
+
+/// The rows of the screen, without trailing spaces.
+fn rows(screen: &Buffer) -> Vec<String> {
+    let area = screen.area;
+    (area.top()..area.bottom())
+        .map(|y| {
+            let row: String = (area.left()..area.right())
+                .map(|x| screen[(x, y)].symbol())
+                .collect();
+            row.trim_end().into()
+        })
+        .collect()
+}
+
+fn check(app: &App, screen: &Buffer, expectation: &Expectation) -> Result<(), String> {
+    let expected_found = |expected: &dyn std::fmt::Debug, found: &dyn std::fmt::Debug| {
+        Err(format!("expected {expected:?}, found {found:?}"))
+    };
+    match expectation {
+        Expectation::Selected(label) => {
+            let selected = app.menu.selected_item().map(|item| item.label.as_str());
+            if selected != Some(label) {
+                return expected_found(label, &selected);
+            }
+        }
+        Expectation::Title(title) => {
+            let found = app.menu.breadcrumbs().join(" › ");
+            if found != *title {
+                return expected_found(title, &found);
+            }
+        }
+        Expectation::Contains(text) => {
+            let rows = rows(screen);
+            if !rows.iter().any(|row| row.contains(text.as_str())) {
+                return Err(format!(
+                    "{text:?} is not on the screen:\n{}",
+                    rows.join("\n")
+                ));
+            }
+        }
+        Expectation::Screen(expected) => {
+            let rows = rows(screen);
+            if rows != *expected {
+                let diff: Vec<_> = rows
+                    .iter()
+                    .enumerate()
+                    .map(|(y, row)| match expected.get(y) {
+                        Some(expected) if expected == row => format!("  {row}"),
+                        Some(expected) => format!("- {expected}\n+ {row}"),
+                        None => format!("+ {row}"),
+                    })
+                    .chain(
+                        expected
+                            .iter()
+                            .skip(rows.len())
+                            .map(|row| format!("- {row}")),
+                    )
+                    .collect();
+                return Err(format!(
+                    "the screen differs (- expected, + found):\n{}",
+                    diff.join("\n")
+                ));
+            }
+        }
+        Expectation::Counter(value) => {
+            let found = app.counters.active().value;
+            if found != *value {
+                return expected_found(value, &found);
+            }
+        }
+        Expectation::Status(status) => {
+            if app.status() != status.as_deref() {
+                return expected_found(status, &app.status());
+            }
+        }
+        Expectation::Choice(labels) => {
+            let choice = (!app.selections().is_empty()).then(|| {
+                let labels: Vec<_> = app.selections().iter().map(|s| s.label.as_str()).collect();
+                labels.join(", ")
+            });
+            if choice.as_ref() != Some(labels) {
+                return expected_found(labels, &choice);
+            }
+        }
+        Expectation::Quit => {
+            if !app.has_quit() {
+                return Err("expected the app to have quit".into());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Message;
+
+    /// Runs every script in `tests/scripts`.
+    #[test]
+    fn scripts() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/scripts");
+        let mut failures = Vec::new();
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path
+                .extension()
+                .is_none_or(|extension| extension != "script")
+            {
+                continue;
+            }
+            let result = Script::load(&path)
+                .map_err(|error| error.to_string())
+                .and_then(|script| script.run(&mut App::default()));
+            if let Err(error) = result {
+                failures.push(format!("{}: {error}", path.display()));
+            }
+        }
+        assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+    }
+
+    #[test]
+    fn load_errors_name_the_file() {
+        let path = Path::new("no/such.script");
+        let error = Script::load(path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert!(error.to_string().starts_with("no/such.script: "), "{error}");
+
+        let path = std::env::temp_dir().join(format!("testo-bad-{}.script", std::process::id()));
+        fs::write(&path, "press nope\n").unwrap();
+        let error = Script::load(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            error.to_string(),
+            format!("{}:1: `press`: unknown key `nope`", path.display())
+        );
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let error = Script::parse("press down\n\npress nope").unwrap_err();
+        assert_eq!(error.to_string(), "3: `press`: unknown key `nope`");
+        let error = Script::parse("| row").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "1: `|`: screen row outside `expect screen`"
+        );
+        let error = Script::parse("expect nothing").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "1: `expect`: unknown check `expect nothing`"
+        );
+
+        let script = Script::parse("# comment\npress down\nexpect selected Three").unwrap();
+        assert_eq!(
+            script.run(&mut App::default()),
+            Err("line 3: expected \"Three\", found Some(\"Two\")".into())
+        );
+    }
+
+    #[test]
+    fn recordings_replay_the_same_events() -> io::Result<()> {
+        let path = std::env::temp_dir().join(format!("testo-{}.script", std::process::id()));
+        let click = MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 3,
+            row: 2,
+            modifiers: KeyModifiers::NONE,
+        };
+        let events = [
+            Event::Resize(40, 8),
+            Event::Key(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL)),
+            Event::Mouse(click),
+            Event::Paste("two\nlines, \"quoted\" ✓".into()),
+        ]
+        .map(AppEvent::Terminal)
+        .into_iter()
+        .chain([AppEvent::Tick, AppEvent::Signal(SIGNALS[0])])
+        .collect::<Vec<_>>();
+
+        let mut recorder = Recorder::create(&path)?;
+        for event in &events {
+            recorder.record(event)?;
+        }
+        recorder.record(&AppEvent::Message(Message::Status("left out".into())))?;
+        drop(recorder);
+        let replayed = Script::load(&path)?.events();
+        fs::remove_file(&path)?;
+
+        assert!(replayed.is_sorted_by_key(|(time, _)| *time));
+        let replayed: Vec<_> = replayed.into_iter().map(|(_, event)| event).collect();
+        assert_eq!(replayed, events);
+        Ok(())
+    }
+
+    #[test]
+    fn recordings_start_with_the_terminal_size() -> io::Result<()> {
+        let path = std::env::temp_dir().join(format!("testo-size-{}.script", std::process::id()));
+        let mut app = App::default();
+        app.record(Recorder::create(&path)?);
+        let quit = AppEvent::Terminal(Event::Key(KeyCode::Char('q').into()));
+        app.replay(vec![(Duration::ZERO, quit)], 0);
+        app.run(&mut Terminal::new(TestBackend::new(40, 8))?)?;
+        drop(app);
+        let recording = fs::read_to_string(&path)?;
+        fs::remove_file(&path)?;
+
+        let commands: Vec<_> = recording
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| split_word(line).1)
+            .collect();
+        assert_eq!(commands, ["resize 40 8", "press q"]);
+        Ok(())
+    }
+
+    #[test]
+    fn screens_show_the_difference() {
+        let script = Script::parse("resize 9 2\nexpect screen\n|    One  █\n|    Three").unwrap();
+        let error = script.run(&mut App::default()).unwrap_err();
+        let expected = [
+            "line 2: the screen differs (- expected, + found):",
+            "     One  █",
+            "-    Three",
+            "+    Two  ┃",
+        ];
+        assert_eq!(error, expected.join("\n"));
+    }
+}
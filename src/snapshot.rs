@@ -0,0 +1,244 @@
+//! Snapshot tests: drive the app with keys on a [`TestBackend`] and compare
+//! the frames, styles included, against files in `tests/snapshots`.
+//!
+//! Run the tests with `TESTO_UPDATE_SNAPSHOTS=1` to write the snapshots
+//! instead of checking them, then review the changes with `git diff`.
+
+use std::{env, fmt::Write, fs, path::PathBuf};
+
+use crossterm::event::{Event, KeyEvent};
+use ratatui::{Terminal, backend::TestBackend, buffer::Buffer, style::Modifier};
+
+use crate::{App, events::AppEvent, keymap::Key};
+
+/// Set to write snapshots instead of comparing against them.
+const UPDATE: &str = "TESTO_UPDATE_SNAPSHOTS";
+
+/// An app on a fake terminal of a fixed size.
+pub struct Harness {
+    app: App,
+    terminal: Terminal<TestBackend>,
+}
+
+impl Harness {
+    pub fn new(app: App, width: u16, height: u16) -> Self {
+        let terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        Self { app, terminal }
+    }
+
+    pub fn app(&mut self) -> &mut App {
+        &mut self.app
+    }
+
+    /// Presses the keys, written as in keymap files and separated by
+    /// spaces, like `down down enter`.
+    pub fn press(&mut self, keys: &str) -> &mut Self {
+        for key in keys.split_whitespace() {
+            let key = Key::parse(key).unwrap_or_else(|error| panic!("{error}"));
+            let event = Event::Key(KeyEvent::from(key));
+            self.app.handle_event(AppEvent::Terminal(event));
+        }
+        self
+    }
+
+    /// Draws the app and compares the frame against the snapshot `name`.
+    #[track_caller]
+    pub fn assert_snapshot(&mut self, name: &str) -> &mut Self {
+        self.terminal.draw(|frame| self.app.draw(frame)).unwrap();
+        let actual = format_buffer(self.terminal.backend().buffer());
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/snapshots")
+            .join(format!("{name}.snap"));
+        if env::var_os(UPDATE).is_some() {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, &actual).unwrap();
+            return self;
+        }
+        let Ok(expected) = fs::read_to_string(&path) else {
+            panic!(
+                "no snapshot at {}, run with {UPDATE}=1 to write it:\n{actual}",
+                path.display()
+            );
+        };
+        if expected != actual {
+            panic!(
+                "frame differs from {} (- expected, + actual), run with {UPDATE}=1 \
+                 to accept it:\n{}",
+                path.display(),
+                diff(&expected, &actual)
+            );
+        }
+        self
+    }
+}
+
+/// Writes the buffer as quoted lines, followed by its styles as runs of
+/// cells with the same style, one run per line as `row columns: style`.
+/// Cells with the default style are left out.
+pub fn format_buffer(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut text = String::new();
+    for y in area.top()..area.bottom() {
+        let line: String = (area.left()..area.right())
+            .map(|x| buffer[(x, y)].symbol())
+            .collect();
+        writeln!(text, "\"{line}\"").unwrap();
+    }
+    text.push_str("styles:\n");
+    for y in area.top()..area.bottom() {
+        let mut x = area.left();
+        while x < area.right() {
+            let style = format_style(&buffer[(x, y)]);
+            let start = x;
+            while x < area.right() && format_style(&buffer[(x, y)]) == style {
+                x += 1;
+            }
+            if !style.is_empty() {
+                writeln!(text, "{y} {start}..{x}: {style}").unwrap();
+            }
+        }
+    }
+    text
+}
+
+fn format_style(cell: &ratatui::buffer::Cell) -> String {
+    let mut parts = Vec::new();
+    if cell.fg != Default::default() {
+        parts.push(format!("fg={}", cell.fg));
+    }
+    if cell.bg != Default::default() {
+        parts.push(format!("bg={}", cell.bg));
+    }
+    if cell.modifier != Modifier::empty() {
+        let names: Vec<_> = cell.modifier.iter_names().map(|(name, _)| name).collect();
+        parts.push(names.join("|").to_lowercase());
+    }
+    parts.join(" ")
+}
+
+/// A line diff of `expected` and `actual`, with unchanged lines indented.
+fn diff(expected: &str, actual: &str) -> String {
+    let old: Vec<_> = expected.lines().collect();
+    let new: Vec<_> = actual.lines().collect();
+    // lengths[i][j] is the length of the longest common subsequence of
+    // old[i..] and new[j..].
+    let mut lengths = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let mut text = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            writeln!(text, "  {}", old[i]).unwrap();
+            (i, j) = (i + 1, j + 1);
+        } else if i < old.len() && (j == new.len() || lengths[i + 1][j] >= lengths[i][j + 1]) {
+            writeln!(text, "- {}", old[i]).unwrap();
+            i += 1;
+        } else {
+            writeln!(text, "+ {}", new[j]).unwrap();
+            j += 1;
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        keymap::Keymap,
+        menu::{Menu, MenuItem},
+    };
+
+    #[test]
+    fn diffs_show_changed_lines() {
+        let expected = "\"a\"\n\"b\"\n\"c\"\n";
+        let actual = "\"a\"\n\"B\"\n\"c\"\n\"d\"\n";
+        assert_eq!(
+            diff(expected, actual),
+            "  \"a\"\n- \"b\"\n+ \"B\"\n  \"c\"\n+ \"d\"\n"
+        );
+    }
+
+    #[test]
+    fn menu() {
+        Harness::new(App::default(), 40, 8)
+            .assert_snapshot("menu")
+            .press("down down")
+            .assert_snapshot("menu_moved");
+    }
+
+    #[test]
+    fn counter() {
+        Harness::new(App::default(), 50, 4)
+            .press("up enter enter right right")
+            .assert_snapshot("counter");
+    }
+
+    #[test]
+    fn submenu_and_filter() {
+        let fruit = ["apple", "banana", "cherry"].map(MenuItem::new).to_vec();
+        let menu = Menu {
+            title: "Select".into(),
+            items: vec![
+                MenuItem {
+                    children: fruit,
+                    ..MenuItem::new("Fruit")
+                },
+                MenuItem::new("Vegetables"),
+            ],
+        };
+        Harness::new(App::new(menu), 40, 8)
+            .press("enter")
+            .assert_snapshot("submenu")
+            .press("/ a n")
+            .assert_snapshot("submenu_filtered");
+    }
+
+    #[test]
+    fn sections() {
+        let menu = Menu {
+            title: "Tools".into(),
+            items: vec![
+                MenuItem::header("Files"),
+                MenuItem::new("Open"),
+                MenuItem {
+                    disabled: Some("nothing open".into()),
+                    ..MenuItem::new("Save")
+                },
+                MenuItem::separator(),
+                MenuItem {
+                    disabled: Some(String::new()),
+                    ..MenuItem::new("Sync")
+                },
+                MenuItem::new("Quit"),
+            ],
+        };
+        Harness::new(App::new(menu), 40, 9)
+            .assert_snapshot("sections")
+            .press("down")
+            .assert_snapshot("sections_moved");
+    }
+
+    #[test]
+    fn multi_picker() {
+        let items = ["apple", "banana", "cherry"].map(String::from).to_vec();
+        Harness::new(App::multi_picker(items), 40, 6)
+            .press("tab down down tab")
+            .assert_snapshot("multi_picker");
+    }
+
+    #[test]
+    fn vim_keys() {
+        let mut harness = Harness::new(App::default(), 40, 8);
+        harness.app().keymap = Keymap::preset("vim").unwrap();
+        harness.press("j j k").assert_snapshot("vim_keys");
+    }
+}
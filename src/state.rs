@@ -0,0 +1,191 @@
+//! Saving the counters between runs.
+//!
+//! The state file is TOML:
+//!
+//! ```toml
+//! active = 1
+//!
+//! [[counter]]
+//! name = "default"
+//! value = 3
+//! ```
+
+use std::{
+    env,
+    fmt::Write as _,
+    fs::{self, File},
+    io::{self, Write as _},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    counter::{Counter, Counters},
+    toml::{self, ConfigError, Table, Value},
+};
+
+/// `$XDG_STATE_HOME/testo/counters.toml`, or
+/// `~/.local/state/testo/counters.toml`.
+pub fn default_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+    Some(base.join(env!("CARGO_PKG_NAME")).join("counters.toml"))
+}
+
+/// Loads the counters saved at `path`, or the default counters if nothing
+/// has been saved yet.
+pub fn load(path: &Path) -> io::Result<Counters> {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Counters::default()),
+        Err(error) => return Err(error),
+    };
+    parse(&source).map_err(|error| error.in_file(path).into())
+}
+
+fn parse(source: &str) -> Result<Counters, ConfigError> {
+    let root = toml::parse(source)?;
+    let mut active = 0;
+    let mut counters = Vec::new();
+    for entry in &root.entries {
+        match entry.key.as_str() {
+            "active" => {
+                active = usize::try_from(entry.integer()?)
+                    .map_err(|_| entry.error("must not be negative"))?;
+            }
+            "counter" => {
+                let Value::TableArray(tables) = &entry.value else {
+                    return Err(entry.invalid_type("an array of tables (`[[counter]]`)"));
+                };
+                for table in tables {
+                    counters.push(parse_counter(table, &counters)?);
+                }
+            }
+            _ => return Err(entry.unknown_key()),
+        }
+    }
+    Ok(Counters::from_saved(counters, active).unwrap_or_default())
+}
+
+/// Parses a counter, whose name must differ from the `previous` ones as
+/// renaming and undoing rely on names being unique.
+fn parse_counter(table: &Table, previous: &[Counter]) -> Result<Counter, ConfigError> {
+    let mut name = None;
+    let mut value = 0;
+    for entry in &table.entries {
+        match entry.key.as_str() {
+            "name" => {
+                let text = entry.string()?;
+                if previous.iter().any(|counter| counter.name == text) {
+                    return Err(entry.error(&format!("there already is a counter named `{text}`")));
+                }
+                name = Some(text);
+            }
+            "value" => value = entry.integer()?,
+            _ => return Err(entry.unknown_key()),
+        }
+    }
+    let Some(name) = name.filter(|name| !name.is_empty()) else {
+        return Err(ConfigError {
+            path: None,
+            line: table.line,
+            key: Some("name".into()),
+            message: "missing required key".into(),
+        });
+    };
+    Ok(Counter { name, value })
+}
+
+fn serialize(counters: &Counters) -> String {
+    let mut source = format!("active = {}\n", counters.active_index());
+    for counter in counters.iter() {
+        let _ = write!(
+            source,
+            "\n[[counter]]\nname = {}\nvalue = {}\n",
+            toml::quote(&counter.name),
+            counter.value
+        );
+    }
+    source
+}
+
+/// Saves the counters to `path` atomically: the state is written to a
+/// temporary file next to it which then replaces the old file, so a crash
+/// leaves either the old or the new state behind, never a mix.
+pub fn save(path: &Path, counters: &Counters) -> io::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(dir)?;
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = dir.join(temp_name);
+
+    let result = File::create(&temp_path).and_then(|mut file| {
+        file.write_all(serialize(counters).as_bytes())?;
+        file.sync_all()
+    });
+    if let Err(error) = result.and_then(|()| fs::rename(&temp_path, path)) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(error);
+    }
+    // Make the rename itself durable. Not every platform can open a
+    // directory, so this is best effort.
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_load() -> io::Result<()> {
+        let dir = env::temp_dir().join(format!("testo-state-{}", std::process::id()));
+        let path = dir.join("nested").join("counters.toml");
+
+        assert_eq!(load(&path)?, Counters::default());
+
+        let mut counters = Counters::default();
+        counters.increment();
+        counters.create("say \"hi\"").unwrap();
+        counters.decrement();
+        save(&path, &counters)?;
+        assert_eq!(load(&path)?, counters);
+        assert_eq!(fs::read_dir(path.parent().unwrap())?.count(), 1);
+
+        fs::write(&path, "active = 0\n[[counter]]\nvalue = \"x\"\n")?;
+        let error = load(&path).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "{}:3: `value`: expected an integer, found a string",
+                path.display()
+            )
+        );
+
+        fs::write(
+            &path,
+            "[[counter]]\nname = \"a\"\n\n[[counter]]\nname = \"b\"\n\n[[counter]]\nname = \"a\"\n",
+        )?;
+        let error = load(&path).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .ends_with(":8: `name`: there already is a counter named `a`")
+        );
+
+        fs::write(&path, "active = -1\n")?;
+        let error = load(&path).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .ends_with(":1: `active`: must not be negative")
+        );
+        fs::write(&path, "active = 0\n[[counter]]\nname = \"a\"\n")?;
+        assert_eq!(load(&path)?.active_index(), 0);
+
+        fs::remove_dir_all(dir)
+    }
+}
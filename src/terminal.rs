@@ -0,0 +1,232 @@
+//! Terminal setup, both on stdout and for when stdin and stdout are not the
+//! terminal, e.g. when the app is used as a picker in a pipeline like
+//! `ls | testo > choice`.
+//!
+//! The terminal is also restored when the app panics, so that the panic
+//! message is readable and the shell is usable afterwards, and can be handed
+//! to the shell or another program for a while.
+
+use std::{
+    fs::File,
+    io::{self, Write, stdout},
+    panic,
+    process::Command,
+    sync::{Mutex, Once, PoisonError},
+};
+
+use crossterm::{
+    cursor::Show,
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use ratatui::{DefaultTerminal, Terminal, TerminalOptions, Viewport, backend::CrosstermBackend};
+
+pub type TtyTerminal = Terminal<CrosstermBackend<File>>;
+
+/// How the terminal was set up, for code that cannot reach the
+/// [`Terminal`], like the panic hook.
+static ACTIVE: Mutex<Option<Setup>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Setup {
+    /// Whether the app draws on `/dev/tty` rather than stdout.
+    tty: bool,
+    /// Whether the app draws on the alternate screen, which inline viewports
+    /// do not.
+    fullscreen: bool,
+}
+
+impl Setup {
+    fn output(self) -> io::Result<Box<dyn Write>> {
+        Ok(if self.tty {
+            Box::new(File::options().write(true).open("/dev/tty")?)
+        } else {
+            Box::new(stdout())
+        })
+    }
+}
+
+fn active() -> Option<Setup> {
+    *ACTIVE.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn set_active(setup: Option<Setup>) {
+    *ACTIVE.lock().unwrap_or_else(PoisonError::into_inner) = setup;
+}
+
+/// Makes panics restore the terminal before the panic is reported. Call it
+/// before setting up the terminal. Only the first call installs the hook.
+pub fn install_panic_hook() {
+    static INSTALLED: Once = Once::new();
+    INSTALLED.call_once(|| {
+        let report = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let _ = restore_active();
+            report(info);
+        }));
+    });
+}
+
+/// Restores the terminal wherever it was set up, if it still is.
+fn restore_active() -> io::Result<()> {
+    let setup = ACTIVE.lock().unwrap_or_else(PoisonError::into_inner).take();
+    match setup {
+        Some(setup) => reset(&mut setup.output()?),
+        None => Ok(()),
+    }
+}
+
+/// Leaves the terminal as it was before the app started, until [`resume`].
+/// The caller must stop reading events first, and redraw everything after.
+pub fn suspend() -> io::Result<()> {
+    match active() {
+        Some(setup) => reset(&mut setup.output()?),
+        None => Ok(()),
+    }
+}
+
+/// Sets the terminal up again after [`suspend`].
+pub fn resume() -> io::Result<()> {
+    let Some(setup) = active() else {
+        return Ok(());
+    };
+    let mut output = setup.output()?;
+    enable_raw_mode()?;
+    if setup.fullscreen {
+        execute!(output, EnterAlternateScreen)?;
+    }
+    execute!(output, EnableMouseCapture, EnableBracketedPaste)
+}
+
+/// A shell command that reads from and writes to the terminal, even when
+/// stdin and stdout are not the terminal.
+pub fn shell_command(command: &str) -> io::Result<Command> {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    if active().is_some_and(|setup| setup.tty) {
+        let tty = File::options().read(true).write(true).open("/dev/tty")?;
+        shell.stdin(tty.try_clone()?).stdout(tty);
+    }
+    Ok(shell)
+}
+
+/// Undoes everything `init` and `init_tty` did, without knowing which.
+fn reset(out: &mut impl Write) -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        out,
+        DisableMouseCapture,
+        DisableBracketedPaste,
+        LeaveAlternateScreen,
+        Show
+    )
+}
+
+/// Draws on stdout and captures the mouse and pastes.
+pub fn init(viewport: Viewport) -> io::Result<DefaultTerminal> {
+    let terminal = match viewport.clone() {
+        Viewport::Fullscreen => ratatui::init(),
+        viewport => ratatui::init_with_options(TerminalOptions { viewport }),
+    };
+    set_active(Some(Setup {
+        tty: false,
+        fullscreen: viewport == Viewport::Fullscreen,
+    }));
+    execute!(stdout(), EnableMouseCapture, EnableBracketedPaste)?;
+    Ok(terminal)
+}
+
+pub fn restore() -> io::Result<()> {
+    set_active(None);
+    execute!(stdout(), DisableMouseCapture, DisableBracketedPaste)?;
+    ratatui::restore();
+    Ok(())
+}
+
+/// Clears an inline viewport before the terminal is restored and writes
+/// `summary` in its place, so that the scrollback shows the result rather
+/// than the last frame. What is printed next starts on the line after it.
+pub fn clear_inline<W: Write>(
+    terminal: &mut Terminal<CrosstermBackend<W>>,
+    summary: Option<&str>,
+) -> io::Result<()> {
+    terminal.clear()?;
+    let backend = terminal.backend_mut();
+    if let Some(summary) = summary {
+        // Raw mode does not turn `\n` into `\r\n`.
+        write!(backend, "{summary}\r\n")?;
+    }
+    backend.flush()
+}
+
+/// Opens `/dev/tty` and draws on it, leaving stdout free for the result.
+/// Crossterm reads events from `/dev/tty` by itself when stdin is not a tty.
+pub fn init_tty(viewport: Viewport) -> io::Result<TtyTerminal> {
+    let mut tty = File::options().read(true).write(true).open("/dev/tty")?;
+    enable_raw_mode()?;
+    set_active(Some(Setup {
+        tty: true,
+        fullscreen: viewport == Viewport::Fullscreen,
+    }));
+    if viewport == Viewport::Fullscreen {
+        execute!(tty, EnterAlternateScreen)?;
+    }
+    execute!(tty, EnableMouseCapture, EnableBracketedPaste)?;
+    Terminal::with_options(CrosstermBackend::new(tty), TerminalOptions { viewport })
+}
+
+pub fn restore_tty(terminal: &mut TtyTerminal) -> io::Result<()> {
+    set_active(None);
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        DisableMouseCapture,
+        DisableBracketedPaste,
+        LeaveAlternateScreen
+    )?;
+    terminal.show_cursor()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process::Command};
+
+    use super::*;
+
+    /// Set in the test process that is made to panic.
+    const PANICKING: &str = "TESTO_TEST_PANICKING";
+
+    /// Panics in a copy of the test binary, as the hook is global and a
+    /// panic is what tests use to fail.
+    #[test]
+    fn panics_restore_the_terminal() -> io::Result<()> {
+        if env::var_os(PANICKING).is_some() {
+            panic::set_hook(Box::new(|_| {
+                let _ = stdout().write_all(b"reported");
+            }));
+            install_panic_hook();
+            set_active(Some(Setup {
+                tty: false,
+                fullscreen: true,
+            }));
+            panic!("oops");
+        }
+
+        let output = Command::new(env::current_exe()?)
+            .args(["--exact", "terminal::tests::panics_restore_the_terminal"])
+            .arg("--nocapture")
+            .env(PANICKING, "1")
+            .output()?;
+        assert!(!output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let restored = stdout
+            .find("\x1b[?1049l")
+            .expect("left the alternate screen");
+        assert!(stdout.contains("\x1b[?25h"), "showed the cursor");
+        assert!(stdout.contains("\x1b[?1000l"), "released the mouse");
+        let reported = stdout.find("reported").expect("reported the panic");
+        assert!(restored < reported, "restored before reporting");
+        Ok(())
+    }
+}
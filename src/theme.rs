@@ -0,0 +1,347 @@
+//! Colors and borders.
+//!
+//! A theme file starts from a built-in theme and restyles elements in
+//! sections named after them:
+//!
+//! ```toml
+//! preset = "dark"
+//! border = "rounded"
+//!
+//! [selected]
+//! fg = "black"
+//! bg = "#87ceeb"
+//! modifiers = ["bold"]
+//! ```
+//!
+//! Colors are names like `red` or `light-blue`, `#rrggbb` or a number from
+//! the 256-color palette. Restyling an element replaces its whole style.
+
+use std::{
+    env, io,
+    path::{Path, PathBuf},
+};
+
+use ratatui::{
+    style::{Color, Modifier, Style, Stylize},
+    symbols::border,
+};
+
+use crate::{
+    menu,
+    toml::{self, ConfigError, Entry, Value},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub border: border::Set,
+    /// Screen and panel titles.
+    pub title: Style,
+    /// The active menu item or counter.
+    pub selected: Style,
+    /// Every other menu item or counter.
+    pub unselected: Style,
+    /// Things that cannot be used, like changes that were undone.
+    pub disabled: Style,
+    /// The menu item under the mouse pointer.
+    pub hovered: Style,
+    /// The characters of a menu item that match the filter.
+    pub matched: Style,
+    /// The keys in the instructions.
+    pub key_hint: Style,
+    /// The line with the result of the last action.
+    pub status: Style,
+    /// Counter values.
+    pub value: Style,
+    /// The labels of text inputs.
+    pub prompt: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            border: border::THICK,
+            title: Style::new().bold(),
+            selected: Style::new().bold().red(),
+            unselected: Style::new(),
+            disabled: Style::new().dim(),
+            hovered: Style::new().underlined(),
+            matched: Style::new().yellow(),
+            key_hint: Style::new().blue().bold(),
+            status: Style::new().italic(),
+            value: Style::new().yellow(),
+            prompt: Style::new().bold(),
+        }
+    }
+}
+
+impl Theme {
+    pub const PRESETS: [&str; 5] = ["default", "light", "dark", "high-contrast", "monochrome"];
+
+    /// One of the built-in themes, see [`Theme::PRESETS`].
+    pub fn preset(name: &str) -> Option<Self> {
+        let default = Self::default();
+        Some(match name {
+            "default" => default,
+            "light" => Self {
+                border: border::PLAIN,
+                title: Style::new().blue().bold(),
+                selected: Style::new().white().on_blue().bold(),
+                unselected: Style::new().black(),
+                disabled: Style::new().gray(),
+                hovered: Style::new().on_gray(),
+                matched: Style::new().magenta().bold(),
+                key_hint: Style::new().blue().bold(),
+                status: Style::new().dark_gray().italic(),
+                value: Style::new().magenta(),
+                prompt: Style::new().blue().bold(),
+            },
+            "dark" => Self {
+                border: border::ROUNDED,
+                title: Style::new().cyan().bold(),
+                selected: Style::new().black().on_cyan().bold(),
+                unselected: Style::new().gray(),
+                disabled: Style::new().dark_gray(),
+                hovered: Style::new().on_dark_gray(),
+                matched: Style::new().light_yellow().bold(),
+                key_hint: Style::new().cyan().bold(),
+                status: Style::new().gray().italic(),
+                value: Style::new().light_yellow(),
+                prompt: Style::new().cyan().bold(),
+            },
+            "high-contrast" => Self {
+                border: border::DOUBLE,
+                title: Style::new().white().bold(),
+                selected: Style::new().black().on_yellow().bold(),
+                unselected: Style::new().white(),
+                disabled: Style::new().gray().crossed_out(),
+                hovered: Style::new().white().underlined(),
+                matched: Style::new().light_yellow().bold().underlined(),
+                key_hint: Style::new().yellow().bold(),
+                status: Style::new().white().bold(),
+                value: Style::new().white().bold(),
+                prompt: Style::new().yellow().bold(),
+            },
+            "monochrome" => Self {
+                selected: Style::new().bold().reversed(),
+                matched: Style::new().underlined(),
+                key_hint: Style::new().bold(),
+                value: Style::new().bold(),
+                ..default
+            },
+            _ => return None,
+        })
+    }
+
+    /// The same theme with only the modifiers, for terminals or users that
+    /// do not want colors. Styles that were only colors get the `fallback`
+    /// modifier instead, so that they still stand out.
+    pub fn without_colors(self) -> Self {
+        let strip = |style: Style, fallback: Modifier| {
+            let stripped = Style {
+                fg: None,
+                bg: None,
+                underline_color: None,
+                ..style
+            };
+            if stripped.add_modifier.is_empty() && stripped != style {
+                stripped.add_modifier(fallback)
+            } else {
+                stripped
+            }
+        };
+        Self {
+            border: self.border,
+            title: strip(self.title, Modifier::BOLD),
+            selected: strip(self.selected, Modifier::REVERSED),
+            unselected: strip(self.unselected, Modifier::empty()),
+            disabled: strip(self.disabled, Modifier::DIM),
+            hovered: strip(self.hovered, Modifier::UNDERLINED),
+            matched: strip(self.matched, Modifier::UNDERLINED),
+            key_hint: strip(self.key_hint, Modifier::BOLD),
+            status: strip(self.status, Modifier::ITALIC),
+            value: strip(self.value, Modifier::BOLD),
+            prompt: strip(self.prompt, Modifier::BOLD),
+        }
+    }
+
+    /// Loads the theme from `path`, or from the user's config directory when
+    /// no path is given. Falls back to the default theme when there is no
+    /// theme file in the config directory.
+    pub fn load(path: Option<&Path>) -> io::Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match default_path().filter(|path| path.exists()) {
+                Some(path) => path,
+                None => return Ok(Self::default()),
+            },
+        };
+        let source = toml::read(&path)?;
+        Self::parse(&source).map_err(|error| error.in_file(&path).into())
+    }
+
+    pub fn parse(source: &str) -> Result<Self, ConfigError> {
+        let root = toml::parse(source)?;
+        let mut theme = match root.get("preset") {
+            Some(entry) => {
+                let name = entry.string()?;
+                Self::preset(&name).ok_or_else(|| {
+                    entry.error(&format!("expected one of {}", Self::PRESETS.join(", ")))
+                })?
+            }
+            None => Self::default(),
+        };
+        for entry in &root.entries {
+            let style = match entry.key.as_str() {
+                "preset" => continue,
+                "border" => {
+                    theme.border = match entry.string()?.as_str() {
+                        "plain" => border::PLAIN,
+                        "rounded" => border::ROUNDED,
+                        "double" => border::DOUBLE,
+                        "thick" => border::THICK,
+                        _ => {
+                            return Err(
+                                entry.error("expected `plain`, `rounded`, `double` or `thick`")
+                            );
+                        }
+                    };
+                    continue;
+                }
+                "title" => &mut theme.title,
+                "selected" => &mut theme.selected,
+                "unselected" => &mut theme.unselected,
+                "disabled" => &mut theme.disabled,
+                "hovered" => &mut theme.hovered,
+                "matched" => &mut theme.matched,
+                "key-hint" => &mut theme.key_hint,
+                "status" => &mut theme.status,
+                "value" => &mut theme.value,
+                "prompt" => &mut theme.prompt,
+                _ => return Err(entry.unknown_key()),
+            };
+            *style = parse_style(entry)?;
+        }
+        Ok(theme)
+    }
+}
+
+fn parse_style(entry: &Entry) -> Result<Style, ConfigError> {
+    let Value::Table(table) = &entry.value else {
+        return Err(entry.invalid_type("a table"));
+    };
+    let mut style = Style::new();
+    for entry in &table.entries {
+        match entry.key.as_str() {
+            "fg" => style.fg = Some(parse_color(entry)?),
+            "bg" => style.bg = Some(parse_color(entry)?),
+            "modifiers" => {
+                let Value::Array(values) = &entry.value else {
+                    return Err(entry.invalid_type("an array"));
+                };
+                for value in values {
+                    let Value::String(name) = value else {
+                        return Err(entry.invalid_type("an array of strings"));
+                    };
+                    style = style.add_modifier(
+                        parse_modifier(name)
+                            .ok_or_else(|| entry.error(&format!("unknown modifier `{name}`")))?,
+                    );
+                }
+            }
+            _ => return Err(entry.unknown_key()),
+        }
+    }
+    Ok(style)
+}
+
+fn parse_color(entry: &Entry) -> Result<Color, ConfigError> {
+    let name = entry.string()?;
+    name.parse()
+        .map_err(|_| entry.error(&format!("unknown color `{name}`")))
+}
+
+fn parse_modifier(name: &str) -> Option<Modifier> {
+    Some(match name {
+        "bold" => Modifier::BOLD,
+        "dim" => Modifier::DIM,
+        "italic" => Modifier::ITALIC,
+        "underlined" => Modifier::UNDERLINED,
+        "slow-blink" => Modifier::SLOW_BLINK,
+        "rapid-blink" => Modifier::RAPID_BLINK,
+        "reversed" => Modifier::REVERSED,
+        "hidden" => Modifier::HIDDEN,
+        "crossed-out" => Modifier::CROSSED_OUT,
+        _ => return None,
+    })
+}
+
+/// Whether the user asked for no colors, see <https://no-color.org>.
+pub fn no_color() -> bool {
+    env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// `$XDG_CONFIG_HOME/testo/theme.toml`, or `~/.config/testo/theme.toml`.
+pub fn default_path() -> Option<PathBuf> {
+    menu::config_dir().map(|dir| dir.join("theme.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_exist() {
+        for name in Theme::PRESETS {
+            assert!(Theme::preset(name).is_some(), "{name}");
+        }
+        assert_eq!(Theme::preset("solarized"), None);
+    }
+
+    #[test]
+    fn parse_theme_file() {
+        let theme = Theme::parse(
+            r##"
+preset = "dark"
+border = "double"
+[selected]
+fg = "#000000"
+bg = "light-blue"
+modifiers = ["bold", "italic"]
+"##,
+        )
+        .unwrap();
+        assert_eq!(theme.border, border::DOUBLE);
+        assert_eq!(
+            theme.selected,
+            Style::new()
+                .fg(Color::Rgb(0, 0, 0))
+                .bg(Color::LightBlue)
+                .bold()
+                .italic()
+        );
+        assert_eq!(theme.title, Theme::preset("dark").unwrap().title);
+
+        let error = Theme::parse("[title]\nfg = \"mauve\"\n").unwrap_err();
+        assert_eq!(error.to_string(), "2: `fg`: unknown color `mauve`");
+        let error = Theme::parse("[title]\nmodifiers = [\"loud\"]\n").unwrap_err();
+        assert_eq!(error.to_string(), "2: `modifiers`: unknown modifier `loud`");
+    }
+
+    #[test]
+    fn without_colors_keeps_modifiers() {
+        let theme = Theme::preset("dark").unwrap().without_colors();
+        assert_eq!(theme.selected, Style::new().bold());
+        assert_eq!(theme.unselected, Style::new());
+        // Hovering was only a background color.
+        assert_eq!(theme.hovered, Style::new().underlined());
+        assert_eq!(theme.disabled, Style::new().dim());
+
+        for name in Theme::PRESETS {
+            let theme = Theme::preset(name).unwrap().without_colors();
+            for style in [theme.selected, theme.hovered, theme.matched, theme.key_hint] {
+                assert_eq!((style.fg, style.bg), (None, None), "{name}");
+                assert!(!style.add_modifier.is_empty(), "{name}: {style:?}");
+            }
+        }
+    }
+}
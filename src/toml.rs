@@ -0,0 +1,575 @@
+//! A small parser for the subset of TOML used by our config files.
+//!
+//! Supported: comments, `key = value` pairs with bare or quoted keys, basic
+//! and literal strings, integers, booleans, single-line arrays, `[table]`
+//! headers and `[[array.of.tables]]` headers. Every value remembers the line
+//! it was defined on so that callers can point at the offending line when
+//! validating.
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Value>),
+    Table(Table),
+    TableArray(Vec<Table>),
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "a string",
+            Value::Integer(_) => "an integer",
+            Value::Boolean(_) => "a boolean",
+            Value::Array(_) => "an array",
+            Value::Table(_) => "a table",
+            Value::TableArray(_) => "an array of tables",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub key: String,
+    pub line: usize,
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    /// The line of the header that opened this table, or 0 for the root.
+    pub line: usize,
+    pub entries: Vec<Entry>,
+}
+
+impl Entry {
+    pub fn string(&self) -> Result<String, ConfigError> {
+        match &self.value {
+            Value::String(value) => Ok(value.clone()),
+            _ => Err(self.invalid_type("a string")),
+        }
+    }
+
+    pub fn boolean(&self) -> Result<bool, ConfigError> {
+        match self.value {
+            Value::Boolean(value) => Ok(value),
+            _ => Err(self.invalid_type("a boolean")),
+        }
+    }
+
+    pub fn integer(&self) -> Result<i64, ConfigError> {
+        match self.value {
+            Value::Integer(value) => Ok(value),
+            _ => Err(self.invalid_type("an integer")),
+        }
+    }
+
+    /// An error about this entry's value.
+    pub fn error(&self, message: &str) -> ConfigError {
+        ConfigError {
+            path: None,
+            line: self.line,
+            key: Some(self.key.clone()),
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_type(&self, expected: &str) -> ConfigError {
+        self.error(&format!(
+            "expected {expected}, found {}",
+            self.value.type_name()
+        ))
+    }
+
+    pub fn unknown_key(&self) -> ConfigError {
+        self.error("unknown key")
+    }
+}
+
+impl Table {
+    pub fn get(&self, key: &str) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.key == key)
+    }
+
+    fn get_mut(&mut self, key: &str) -> Option<&mut Entry> {
+        self.entries.iter_mut().find(|entry| entry.key == key)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// An error in a config file, pointing at the line and key that caused it.
+#[derive(Debug)]
+pub struct ConfigError {
+    pub path: Option<PathBuf>,
+    pub line: usize,
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl ConfigError {
+    pub fn in_file(self, path: &Path) -> Self {
+        Self {
+            path: Some(path.to_path_buf()),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "{}:", path.display())?;
+        }
+        write!(f, "{}: ", self.line)?;
+        if let Some(key) = &self.key {
+            write!(f, "`{key}`: ")?;
+        }
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<ParseError> for ConfigError {
+    fn from(error: ParseError) -> Self {
+        Self {
+            path: None,
+            line: error.line,
+            key: None,
+            message: error.message,
+        }
+    }
+}
+
+impl From<ConfigError> for io::Error {
+    fn from(error: ConfigError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// Reads a config file, naming it in the error if that fails.
+pub fn read(path: &Path) -> io::Result<String> {
+    fs::read_to_string(path)
+        .map_err(|error| io::Error::new(error.kind(), format!("{}: {error}", path.display())))
+}
+
+pub fn parse(source: &str) -> Result<Table, ParseError> {
+    let mut root = Table::default();
+    // Path of the table that `key = value` lines currently write into.
+    let mut current: Vec<String> = Vec::new();
+
+    for (index, raw) in source.lines().enumerate() {
+        let line = index + 1;
+        let error = |message: String| ParseError { line, message };
+        let mut cursor = Cursor::new(raw);
+        cursor.skip_whitespace();
+        if cursor.at_end_of_line() {
+            continue;
+        }
+
+        if cursor.eat("[[") {
+            let path = cursor.key_path().map_err(error)?;
+            cursor.expect("]]").map_err(error)?;
+            cursor.expect_end_of_line().map_err(error)?;
+            let (last, parents) = path.split_last().expect("key paths are never empty");
+            let parent = resolve(&mut root, parents, line)?;
+            match parent.get_mut(last) {
+                Some(Entry {
+                    value: Value::TableArray(tables),
+                    ..
+                }) => tables.push(Table {
+                    line,
+                    entries: Vec::new(),
+                }),
+                Some(entry) => {
+                    return Err(error(format!(
+                        "`{last}` is already defined as {} on line {}",
+                        entry.value.type_name(),
+                        entry.line
+                    )));
+                }
+                None => parent.entries.push(Entry {
+                    key: last.clone(),
+                    line,
+                    value: Value::TableArray(vec![Table {
+                        line,
+                        entries: Vec::new(),
+                    }]),
+                }),
+            }
+            current = path;
+        } else if cursor.eat("[") {
+            let path = cursor.key_path().map_err(error)?;
+            cursor.expect("]").map_err(error)?;
+            cursor.expect_end_of_line().map_err(error)?;
+            let (last, parents) = path.split_last().expect("key paths are never empty");
+            let parent = resolve(&mut root, parents, line)?;
+            if let Some(entry) = parent.get(last) {
+                return Err(error(format!(
+                    "`{last}` is already defined on line {}",
+                    entry.line
+                )));
+            }
+            parent.entries.push(Entry {
+                key: last.clone(),
+                line,
+                value: Value::Table(Table {
+                    line,
+                    entries: Vec::new(),
+                }),
+            });
+            current = path;
+        } else {
+            let key = cursor.key().map_err(error)?;
+            cursor.skip_whitespace();
+            if cursor.peek() == Some('.') {
+                return Err(error("dotted keys are not supported".into()));
+            }
+            cursor.expect("=").map_err(error)?;
+            cursor.skip_whitespace();
+            let value = cursor.value().map_err(error)?;
+            cursor.expect_end_of_line().map_err(error)?;
+            let table = resolve(&mut root, &current, line)?;
+            if let Some(entry) = table.get(&key) {
+                return Err(error(format!(
+                    "duplicate key `{key}`, first defined on line {}",
+                    entry.line
+                )));
+            }
+            table.entries.push(Entry { key, line, value });
+        }
+    }
+    Ok(root)
+}
+
+/// Formats `value` as a TOML basic string, escaping as needed.
+pub fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Walks `path` from `root`, descending into the last element of arrays of
+/// tables, and creating implicit tables along the way.
+fn resolve<'a>(
+    root: &'a mut Table,
+    path: &[String],
+    line: usize,
+) -> Result<&'a mut Table, ParseError> {
+    let mut table = root;
+    for key in path {
+        if table.get(key).is_none() {
+            table.entries.push(Entry {
+                key: key.clone(),
+                line,
+                value: Value::Table(Table {
+                    line,
+                    entries: Vec::new(),
+                }),
+            });
+        }
+        let entry = table.get_mut(key).expect("entry was just inserted");
+        table = match &mut entry.value {
+            Value::Table(inner) => inner,
+            Value::TableArray(tables) => {
+                tables.last_mut().expect("arrays of tables are never empty")
+            }
+            other => {
+                return Err(ParseError {
+                    line,
+                    message: format!(
+                        "`{key}` is {} (line {}), not a table",
+                        other.type_name(),
+                        entry.line
+                    ),
+                });
+            }
+        };
+    }
+    Ok(table)
+}
+
+struct Cursor<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(line: &'a str) -> Self {
+        Self { rest: line }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest.chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.rest = &self.rest[c.len_utf8()..];
+        Some(c)
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(format!(
+                "expected `{token}`, found {}",
+                self.describe_next()
+            ))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start_matches([' ', '\t']);
+    }
+
+    fn at_end_of_line(&self) -> bool {
+        self.rest.is_empty() || self.rest.starts_with('#')
+    }
+
+    fn expect_end_of_line(&mut self) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.at_end_of_line() {
+            Ok(())
+        } else {
+            Err(format!("unexpected {} after value", self.describe_next()))
+        }
+    }
+
+    fn describe_next(&self) -> String {
+        match self.peek() {
+            Some(c) => format!("`{c}`"),
+            None => "end of line".into(),
+        }
+    }
+
+    fn key_path(&mut self) -> Result<Vec<String>, String> {
+        let mut path = Vec::new();
+        loop {
+            self.skip_whitespace();
+            path.push(self.key()?);
+            self.skip_whitespace();
+            if !self.eat(".") {
+                return Ok(path);
+            }
+        }
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some('"') => self.basic_string(),
+            Some('\'') => self.literal_string(),
+            _ => {
+                let end = self
+                    .rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                    .unwrap_or(self.rest.len());
+                if end == 0 {
+                    return Err(format!("expected a key, found {}", self.describe_next()));
+                }
+                let (key, rest) = self.rest.split_at(end);
+                self.rest = rest;
+                Ok(key.to_string())
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => self.array(),
+            Some('t' | 'f') => {
+                if self.eat("true") {
+                    Ok(Value::Boolean(true))
+                } else if self.eat("false") {
+                    Ok(Value::Boolean(false))
+                } else {
+                    Err(format!("expected a value, found {}", self.describe_next()))
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '-' || c == '+' => self.integer(),
+            _ => Err(format!("expected a value, found {}", self.describe_next())),
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.bump();
+        let mut values = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.eat("]") {
+                return Ok(Value::Array(values));
+            }
+            if self.rest.is_empty() {
+                return Err("unterminated array (arrays must fit on one line)".into());
+            }
+            values.push(self.value()?);
+            self.skip_whitespace();
+            if !self.eat(",") {
+                self.skip_whitespace();
+                return self.expect("]").map(|()| Value::Array(values));
+            }
+        }
+    }
+
+    fn integer(&mut self) -> Result<Value, String> {
+        let end = self
+            .rest
+            .char_indices()
+            .find(|&(i, c)| !(c.is_ascii_digit() || c == '_' || (i == 0 && (c == '-' || c == '+'))))
+            .map_or(self.rest.len(), |(i, _)| i);
+        let (digits, rest) = self.rest.split_at(end);
+        let number = digits
+            .replace('_', "")
+            .parse()
+            .map_err(|_| format!("invalid integer `{digits}`"))?;
+        self.rest = rest;
+        Ok(Value::Integer(number))
+    }
+
+    fn literal_string(&mut self) -> Result<String, String> {
+        self.bump();
+        match self.rest.find('\'') {
+            Some(end) => {
+                let value = self.rest[..end].to_string();
+                self.rest = &self.rest[end + 1..];
+                Ok(value)
+            }
+            None => Err("unterminated string".into()),
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, String> {
+        self.bump();
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                None => return Err("unterminated string".into()),
+                Some('"') => return Ok(value),
+                Some('\\') => match self.bump() {
+                    Some('"') => value.push('"'),
+                    Some('\\') => value.push('\\'),
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('r') => value.push('\r'),
+                    Some('u') => {
+                        let hex = self.rest.get(..4).ok_or("truncated `\\u` escape")?;
+                        let c = u32::from_str_radix(hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| format!("invalid escape `\\u{hex}`"))?;
+                        self.rest = &self.rest[4..];
+                        value.push(c);
+                    }
+                    Some(c) => return Err(format!("invalid escape `\\{c}`")),
+                    None => return Err("unterminated string".into()),
+                },
+                Some(c) => value.push(c),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_arrays_of_tables() {
+        let table = parse(
+            r#"
+            title = "Main" # trailing comment
+            [[item]]
+            label = 'One'
+            [[item.item]]
+            label = "Child \"quoted\""
+            tags = ["a", 1, true]
+            [[item]]
+            label = "Two"
+            "#,
+        )
+        .unwrap();
+
+        let Some(Entry {
+            value: Value::TableArray(items),
+            ..
+        }) = table.get("item")
+        else {
+            panic!("expected an array of tables");
+        };
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].line, 3);
+        let Some(Entry {
+            value: Value::TableArray(children),
+            ..
+        }) = items[0].get("item")
+        else {
+            panic!("expected nested items");
+        };
+        assert_eq!(
+            children[0].get("label").unwrap().value,
+            Value::String("Child \"quoted\"".into())
+        );
+        assert_eq!(children[0].get("tags").unwrap().line, 7);
+    }
+
+    #[test]
+    fn quoted_strings_parse_back() {
+        let value = "say \"hi\"\\\n\t\u{1}é";
+        let table = parse(&format!("key = {}", quote(value))).unwrap();
+        assert_eq!(table.get("key").unwrap().value, Value::String(value.into()));
+    }
+
+    #[test]
+    fn reports_line_of_error() {
+        let error = parse("a = 1\nb = \"oops\nc = 3").unwrap_err();
+        assert_eq!(error.line, 2);
+
+        let error = parse("a = 1\na = 2").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert!(error.message.contains("line 1"));
+    }
+}
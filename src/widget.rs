@@ -0,0 +1,788 @@
+//! The menu as a widget that other ratatui apps can embed.
+//!
+//! [`MenuState`] holds a [`Menu`] and where the user is in it: the open
+//! submenus, the selected item, the filter and the scroll position. Only
+//! selectable items are ever selected: moving skips separators, headers and
+//! disabled items. Checkboxes and radio buttons keep whether they are
+//! checked in the state too, see [`MenuState::toggle`].
+//! [`MenuWidget`] draws it:
+//!
+//! ```no_run
+//! # use ratatui::{Frame, widgets::Block};
+//! # use testo::{menu::Menu, widget::{MenuState, MenuWidget}};
+//! # fn draw(frame: &mut Frame, state: &mut MenuState) {
+//! let widget = MenuWidget::default().block(Block::bordered());
+//! frame.render_stateful_widget(widget, frame.area(), state);
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::Style,
+    text::{Line, Span, Text},
+    widgets::{
+        Block, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, StatefulWidget, Widget,
+    },
+};
+
+use crate::{
+    filter::Filter,
+    menu::{ItemKind, Menu, MenuItem, Toggle},
+    theme::Theme,
+};
+
+/// Where the user is in a menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MenuState {
+    /// The title of the root menu.
+    pub title: String,
+    items: Vec<MenuItem>,
+    /// Indices of the items whose submenus are open, from the root down.
+    stack: Vec<usize>,
+    /// The selection of each submenu we have left, keyed by its path.
+    remembered_selections: HashMap<Vec<usize>, usize>,
+    selected: usize,
+    /// The first item that was drawn.
+    offset: usize,
+    /// The query narrowing down the items while it is being typed.
+    filter: Option<Filter>,
+    /// The item under the mouse pointer.
+    hovered: Option<usize>,
+    /// Where the items were drawn last, to map mouse positions back to them.
+    list_area: Rect,
+}
+
+impl MenuState {
+    pub fn new(menu: Menu) -> Self {
+        Self {
+            title: menu.title,
+            selected: first_selectable(&menu.items),
+            items: menu.items,
+            stack: Vec::new(),
+            remembered_selections: HashMap::new(),
+            offset: 0,
+            filter: None,
+            hovered: None,
+            list_area: Rect::default(),
+        }
+    }
+
+    /// The items of the menu level that is currently shown.
+    pub fn items(&self) -> &[MenuItem] {
+        self.stack
+            .iter()
+            .fold(&self.items, |items, &index| &items[index].children)
+    }
+
+    fn items_mut(&mut self) -> &mut [MenuItem] {
+        self.stack
+            .iter()
+            .fold(&mut self.items, |items, &index| &mut items[index].children)
+    }
+
+    /// The indices of the items whose submenus are open, from the root down.
+    pub fn path(&self) -> &[usize] {
+        &self.stack
+    }
+
+    /// The labels from the root menu down to the current level.
+    pub fn breadcrumbs(&self) -> Vec<&str> {
+        let mut items = &self.items;
+        let mut crumbs = vec![self.title.as_str()];
+        for &index in &self.stack {
+            crumbs.push(&items[index].label);
+            items = &items[index].children;
+        }
+        crumbs
+    }
+
+    /// The index of the selected item in [`MenuState::items`].
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// The selected item, unless nothing matches the filter or nothing can
+    /// be selected.
+    pub fn selected_item(&self) -> Option<&MenuItem> {
+        if self.view_len() == 0 {
+            return None;
+        }
+        self.items()
+            .get(self.selected)
+            .filter(|item| item.is_selectable())
+    }
+
+    /// Selects the item at `index` in [`MenuState::items`], or the last one
+    /// if there are fewer. If that item cannot be selected, the next one
+    /// that can is, or else the previous one.
+    pub fn select(&mut self, index: usize) {
+        self.filter = None;
+        self.selected = index.min(self.items().len().saturating_sub(1));
+        self.select_position(self.selected);
+    }
+
+    /// Opens the submenus at `path` and selects `index` in the last one.
+    pub fn show(&mut self, path: &[usize], index: usize) {
+        path.clone_into(&mut self.stack);
+        self.hovered = None;
+        self.select(index);
+    }
+
+    /// How many items are shown: all of them, or the filter matches.
+    pub fn view_len(&self) -> usize {
+        match &self.filter {
+            Some(filter) => filter.matches().len(),
+            None => self.items().len(),
+        }
+    }
+
+    /// The index of the item shown at `position`.
+    pub fn view_item(&self, position: usize) -> usize {
+        match &self.filter {
+            Some(filter) => filter.matches()[position].index,
+            None => position,
+        }
+    }
+
+    /// Where the selected item is shown.
+    pub fn view_position(&self) -> usize {
+        match &self.filter {
+            Some(filter) => filter.selected,
+            None => self.selected,
+        }
+    }
+
+    /// Whether the item shown at `position` can be selected.
+    pub fn is_selectable(&self, position: usize) -> bool {
+        self.items()[self.view_item(position)].is_selectable()
+    }
+
+    /// Selects the item shown at `position`, or the last one if there are
+    /// fewer. If that item cannot be selected, the next one that can is, or
+    /// else the previous one. Does nothing if no item can be selected.
+    pub fn select_position(&mut self, position: usize) {
+        let Some(last) = self.view_len().checked_sub(1) else {
+            return;
+        };
+        let position = position.min(last);
+        let Some(position) = (position..=last)
+            .chain((0..position).rev())
+            .find(|&position| self.is_selectable(position))
+        else {
+            return;
+        };
+        self.selected = self.view_item(position);
+        if let Some(filter) = &mut self.filter {
+            filter.selected = position;
+        }
+    }
+
+    /// Selects the previous selectable item, wrapping around to the last.
+    pub fn up(&mut self) {
+        let len = self.view_len();
+        let start = self.view_position();
+        self.step((1..=len).map(|n| (start + len - n) % len));
+    }
+
+    /// Selects the next selectable item, wrapping around to the first.
+    pub fn down(&mut self) {
+        let len = self.view_len();
+        let start = self.view_position();
+        self.step((1..=len).map(|n| (start + n) % len));
+    }
+
+    /// Selects the first selectable item of `positions`, if any.
+    fn step(&mut self, mut positions: impl Iterator<Item = usize>) {
+        if let Some(position) = positions.find(|&position| self.is_selectable(position)) {
+            self.select_position(position);
+        }
+    }
+
+    /// How many items a page up or down moves by: one screenful.
+    pub fn page_size(&self) -> usize {
+        usize::from(self.list_area.height).max(1)
+    }
+
+    /// Opens the submenu of the selected item. Returns `false` if it has
+    /// none.
+    pub fn open(&mut self) -> bool {
+        if self
+            .selected_item()
+            .is_none_or(|item| item.children.is_empty())
+        {
+            return false;
+        }
+        self.stack.push(self.selected);
+        self.offset = 0;
+        self.filter = None;
+        self.hovered = None;
+        self.selected = match self.remembered_selections.get(&self.stack) {
+            Some(&selected) => selected,
+            None => first_selectable(self.items()),
+        };
+        true
+    }
+
+    /// Goes back to the parent menu. Returns `false` at the root.
+    pub fn close(&mut self) -> bool {
+        let Some(parent_selection) = self.stack.last().copied() else {
+            return false;
+        };
+        self.remembered_selections
+            .insert(self.stack.clone(), self.selected);
+        self.stack.pop();
+        self.offset = 0;
+        self.filter = None;
+        self.hovered = None;
+        self.selected = parent_selection;
+        true
+    }
+
+    pub fn filter(&self) -> Option<&Filter> {
+        self.filter.as_ref()
+    }
+
+    /// Starts filtering with an empty query.
+    pub fn start_filter(&mut self) {
+        let mut filter = Filter::new(self.items());
+        let position = filter
+            .matches()
+            .iter()
+            .position(|m| m.index == self.selected);
+        filter.selected = position.unwrap_or(0);
+        self.filter = Some(filter);
+        if position.is_none() {
+            self.select_position(0);
+        }
+    }
+
+    /// Adds `c` to the query and selects the best match.
+    pub fn push_filter(&mut self, c: char) {
+        let Some(mut filter) = self.filter.take() else {
+            return;
+        };
+        filter.push(c, self.items());
+        self.filter = Some(filter);
+        self.select_best_match();
+    }
+
+    /// Removes the last character of the query and selects the best match.
+    /// Deleting past the start closes the filter.
+    pub fn pop_filter(&mut self) {
+        if self.filter.as_mut().is_some_and(Filter::pop) {
+            self.select_best_match();
+        } else {
+            self.close_filter();
+        }
+    }
+
+    /// Shows all items again, keeping the item that was selected in the
+    /// filter selected.
+    pub fn close_filter(&mut self) {
+        self.filter = None;
+    }
+
+    fn select_best_match(&mut self) {
+        if let Some(best) = self.filter.as_ref().and_then(|f| f.matches().first()) {
+            self.selected = best.index;
+        }
+    }
+
+    /// Checks or unchecks the selected checkbox, or checks the selected
+    /// radio button and unchecks the others in its group. Returns `false`
+    /// if the selected item is neither.
+    pub fn toggle(&mut self) -> bool {
+        let Some(toggle) = self.selected_item().and_then(|item| item.toggle.clone()) else {
+            return false;
+        };
+        let selected = self.selected;
+        let items = self.items_mut();
+        match toggle {
+            Toggle::Checkbox => items[selected].checked = !items[selected].checked,
+            Toggle::Radio { .. } => {
+                for (i, item) in items.iter_mut().enumerate() {
+                    if item.toggle.as_ref() == Some(&toggle) {
+                        item.checked = i == selected;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// The indices of the checked items of the menu level that is shown.
+    pub fn checked(&self) -> Vec<usize> {
+        let items = self.items().iter().enumerate();
+        items
+            .filter(|(_, item)| item.checked)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Checks the checkboxes and radio buttons of the menu level that is
+    /// shown at `indices`, and unchecks the others.
+    pub fn set_checked(&mut self, indices: &[usize]) {
+        for (i, item) in self.items_mut().iter_mut().enumerate() {
+            if item.toggle.is_some() {
+                item.checked = indices.contains(&i);
+            }
+        }
+    }
+
+    /// The checked items of all menu levels, each with its index in its
+    /// menu, in the order they are shown.
+    pub fn checked_items(&self) -> Vec<(usize, &MenuItem)> {
+        fn collect<'a>(items: &'a [MenuItem], checked: &mut Vec<(usize, &'a MenuItem)>) {
+            for (index, item) in items.iter().enumerate() {
+                if item.checked {
+                    checked.push((index, item));
+                }
+                collect(&item.children, checked);
+            }
+        }
+        let mut checked = Vec::new();
+        collect(&self.items, &mut checked);
+        checked
+    }
+
+    /// The item under the mouse pointer.
+    pub fn hovered(&self) -> Option<usize> {
+        self.hovered
+    }
+
+    /// Notes that the mouse pointer is over the item shown at `position`, or
+    /// over none. Items that cannot be selected are not hovered either.
+    pub fn hover(&mut self, position: Option<usize>) {
+        self.hovered = position
+            .filter(|&position| position < self.view_len() && self.is_selectable(position))
+            .map(|position| self.view_item(position));
+    }
+
+    /// The first item that was drawn.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Where the item drawn at the terminal position is shown, if any.
+    pub fn position_at(&self, column: u16, row: u16) -> Option<usize> {
+        if !self.list_area.contains((column, row).into()) {
+            return None;
+        }
+        let position = self.offset + usize::from(row - self.list_area.y);
+        (position < self.view_len()).then_some(position)
+    }
+
+    /// The first item to draw in `height` rows so that the selected item is
+    /// visible, scrolling as little as possible from the last offset.
+    fn visible_offset(&self, height: usize) -> usize {
+        let len = self.view_len();
+        let position = self.view_position();
+        let offset = self.offset.min(len.saturating_sub(height));
+        if position < offset {
+            position
+        } else if height > 0 && position >= offset + height {
+            position + 1 - height
+        } else {
+            offset
+        }
+    }
+}
+
+/// Draws a [`MenuState`]: the items of the current level, a scrollbar when
+/// they do not fit, and the filter query while one is typed.
+#[derive(Debug, Clone, Default)]
+pub struct MenuWidget<'a> {
+    block: Option<Block<'a>>,
+    footer: Option<Line<'a>>,
+    theme: Theme,
+}
+
+impl<'a> MenuWidget<'a> {
+    /// Draws the menu inside `block`. The scrollbar goes on its right border.
+    pub fn block(mut self, block: Block<'a>) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    /// A line below the items, like a status message.
+    pub fn footer(mut self, footer: impl Into<Line<'a>>) -> Self {
+        self.footer = Some(footer.into());
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+}
+
+impl StatefulWidget for MenuWidget<'_> {
+    type State = MenuState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut MenuState) {
+        let inner = match &self.block {
+            Some(block) => block.inner(area),
+            None => area,
+        };
+        let [list_area, filter_area, footer_area] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(state.filter.is_some().into()),
+            Constraint::Length(self.footer.is_some().into()),
+        ])
+        .areas(inner);
+        let height = usize::from(list_area.height);
+        state.list_area = list_area;
+        state.offset = state.visible_offset(height);
+
+        // Only the visible items are turned into lines, so long menus cost
+        // no more to draw than short ones.
+        let theme = &self.theme;
+        let items = state.items();
+        let len = state.view_len();
+        let offset = state.offset;
+        let lines: Vec<Line> = (offset..len.min(offset + height))
+            .map(|position| {
+                let i = state.view_item(position);
+                let item = &items[i];
+                let mut line = match &state.filter {
+                    Some(filter) => highlight_matches(
+                        &item.label,
+                        &filter.matches()[position].positions,
+                        theme.matched,
+                    ),
+                    None => Line::from(item.label.as_str()),
+                };
+                match item.kind {
+                    ItemKind::Separator => {
+                        let width = usize::from(list_area.width);
+                        return Line::styled(
+                            theme.border.horizontal_top.repeat(width),
+                            theme.disabled,
+                        );
+                    }
+                    ItemKind::Header => return line.patch_style(theme.title),
+                    ItemKind::Item => {}
+                }
+                let marker = match (&item.toggle, item.checked) {
+                    (Some(Toggle::Checkbox), true) => "[x] ",
+                    (Some(Toggle::Checkbox), false) => "[ ] ",
+                    (Some(Toggle::Radio { .. }), true) => "(x) ",
+                    (Some(Toggle::Radio { .. }), false) => "( ) ",
+                    (None, _) => "",
+                };
+                if !marker.is_empty() {
+                    line.spans.insert(0, Span::raw(marker));
+                }
+                if let Some(reason) = &item.disabled {
+                    let mut line = line.patch_style(theme.disabled);
+                    if !reason.is_empty() {
+                        line.push_span(Span::styled(format!(" ({reason})"), theme.disabled));
+                    }
+                    return line;
+                }
+                if state.selected == i {
+                    line.patch_style(theme.selected)
+                } else if state.hovered == Some(i) {
+                    line.patch_style(theme.unselected.patch(theme.hovered))
+                } else {
+                    line.patch_style(theme.unselected)
+                }
+            })
+            .collect();
+
+        if let Some(block) = self.block {
+            block.render(area, buf);
+        }
+        Paragraph::new(Text::from(lines))
+            .centered()
+            .render(list_area, buf);
+        if len > height && area.width > 0 {
+            // Drawn over the right border, next to the items.
+            let scrollbar_area = Rect {
+                x: area.right().saturating_sub(1),
+                width: 1,
+                ..list_area
+            };
+            let mut scrollbar_state = ScrollbarState::new(len - height)
+                .position(offset)
+                .viewport_content_length(height);
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None)
+                .track_symbol(Some(theme.border.vertical_right))
+                .render(scrollbar_area, buf, &mut scrollbar_state);
+        }
+        if let Some(filter) = &state.filter {
+            Line::from(vec![
+                Span::styled("/", theme.prompt),
+                filter.query().into(),
+                "█".into(),
+                Span::styled(format!(" {len}/{}", filter.total()), theme.disabled),
+            ])
+            .centered()
+            .render(filter_area, buf);
+        }
+        if let Some(footer) = self.footer {
+            footer.render(footer_area, buf);
+        }
+    }
+}
+
+/// The index of the first item that can be selected, or 0 if none can.
+fn first_selectable(items: &[MenuItem]) -> usize {
+    items.iter().position(MenuItem::is_selectable).unwrap_or(0)
+}
+
+/// The label with the characters at `positions` in the `matched` style.
+fn highlight_matches<'a>(label: &'a str, positions: &[usize], matched_style: Style) -> Line<'a> {
+    let mut spans = Vec::new();
+    let mut start = 0;
+    let mut matched = false;
+    let span = |text, matched| {
+        if matched {
+            Span::styled(text, matched_style)
+        } else {
+            Span::from(text)
+        }
+    };
+    for (n, (byte, _)) in label.char_indices().enumerate() {
+        let is_match = positions.binary_search(&n).is_ok();
+        if is_match != matched {
+            if byte > start {
+                spans.push(span(&label[start..byte], matched));
+            }
+            start = byte;
+            matched = is_match;
+        }
+    }
+    spans.push(span(&label[start..], matched));
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fruit() -> MenuState {
+        MenuState::new(Menu {
+            title: "Fruit".into(),
+            items: ["apple", "banana", "cherry"]
+                .into_iter()
+                .map(MenuItem::new)
+                .collect(),
+        })
+    }
+
+    #[test]
+    fn renders_on_its_own() {
+        let mut state = fruit();
+        state.down();
+        let mut buf = Buffer::empty(Rect::new(0, 0, 12, 4));
+        MenuWidget::default()
+            .block(Block::bordered())
+            .render(buf.area, &mut buf, &mut state);
+        let row = |y| (0..12).map(|x| buf[(x, y)].symbol()).collect::<String>();
+        assert_eq!(
+            [row(0), row(1), row(2), row(3)],
+            [
+                "┌──────────┐",
+                "│   apple  █",
+                "│  banana  █",
+                "└──────────┘",
+            ]
+        );
+        assert_eq!(state.offset(), 0);
+        assert_eq!(state.position_at(5, 2), Some(1));
+        assert_eq!(state.page_size(), 2);
+    }
+
+    #[test]
+    fn filters_and_navigates() {
+        let mut state = fruit();
+        state.start_filter();
+        state.push_filter('e');
+        state.push_filter('r');
+        assert_eq!(
+            state.selected_item().map(|item| &*item.label),
+            Some("cherry")
+        );
+        state.push_filter('x');
+        assert_eq!(state.selected_item(), None);
+        state.pop_filter();
+        state.pop_filter();
+        state.pop_filter();
+        assert!(state.filter().is_some());
+        state.pop_filter();
+        assert_eq!(state.filter(), None);
+        assert_eq!(state.selected(), 0);
+
+        state.down();
+        assert_eq!(state.selected(), 1);
+        state.up();
+        state.up();
+        assert_eq!(state.selected(), 2);
+        assert!(!state.open());
+        assert!(!state.close());
+    }
+
+    #[test]
+    fn tiny_lists() {
+        let mut empty = MenuState::new(Menu {
+            title: "Empty".into(),
+            items: Vec::new(),
+        });
+        empty.up();
+        empty.down();
+        empty.select(3);
+        empty.select_position(3);
+        empty.hover(Some(0));
+        assert_eq!(empty.selected(), 0);
+        assert_eq!(empty.selected_item(), None);
+        assert_eq!(empty.hovered(), None);
+        assert!(!empty.open());
+
+        let mut single = MenuState::new(Menu {
+            title: "Single".into(),
+            items: vec![MenuItem::new("only")],
+        });
+        single.up();
+        assert_eq!(single.selected(), 0);
+        single.down();
+        single.select(3);
+        assert_eq!(single.selected(), 0);
+
+        // Moving through a filter that matches nothing keeps the selection.
+        let mut state = fruit();
+        state.down();
+        state.start_filter();
+        state.down();
+        assert_eq!(state.selected(), 2);
+        state.push_filter('x');
+        state.up();
+        state.down();
+        state.select_position(0);
+        state.hover(Some(0));
+        assert_eq!(state.selected(), 2);
+        assert_eq!(state.selected_item(), None);
+        assert_eq!(state.hovered(), None);
+        assert!(!state.open());
+    }
+
+    #[test]
+    fn skips_what_cannot_be_selected() {
+        let mut state = MenuState::new(Menu {
+            title: "Sections".into(),
+            items: vec![
+                MenuItem::header("Fruit"),
+                MenuItem::new("apple"),
+                MenuItem {
+                    disabled: Some("out of season".into()),
+                    ..MenuItem::new("cherry")
+                },
+                MenuItem::separator(),
+                MenuItem::new("bread"),
+                MenuItem::separator(),
+            ],
+        });
+        assert_eq!(state.selected(), 1);
+        state.down();
+        assert_eq!(state.selected(), 4);
+        state.down();
+        assert_eq!(state.selected(), 1);
+        state.up();
+        assert_eq!(state.selected(), 4);
+        state.select_position(0);
+        assert_eq!(state.selected(), 1);
+        state.select(usize::MAX);
+        assert_eq!(state.selected(), 4);
+        state.select(2);
+        assert_eq!(state.selected(), 4);
+        state.hover(Some(3));
+        assert_eq!(state.hovered(), None);
+
+        // Filtering only matches the items that can be selected.
+        state.start_filter();
+        assert_eq!(state.view_len(), 2);
+        assert_eq!(state.view_position(), 1);
+        state.push_filter('e');
+        assert_eq!(
+            state.selected_item().map(|item| &*item.label),
+            Some("apple")
+        );
+
+        let mut nothing = MenuState::new(Menu {
+            title: "Nothing".into(),
+            items: vec![MenuItem::header("Empty"), MenuItem::separator()],
+        });
+        nothing.down();
+        nothing.up();
+        nothing.select_position(1);
+        nothing.start_filter();
+        assert_eq!(nothing.view_len(), 0);
+        assert_eq!(nothing.selected(), 0);
+        assert_eq!(nothing.selected_item(), None);
+    }
+
+    #[test]
+    fn toggles() {
+        let radio = |label| MenuItem {
+            toggle: Some(Toggle::Radio {
+                group: "size".into(),
+            }),
+            ..MenuItem::new(label)
+        };
+        let mut state = MenuState::new(Menu {
+            title: "Options".into(),
+            items: vec![
+                MenuItem {
+                    toggle: Some(Toggle::Checkbox),
+                    ..MenuItem::new("verbose")
+                },
+                radio("small"),
+                radio("large"),
+                MenuItem::new("done"),
+            ],
+        });
+        let checked = |state: &MenuState| {
+            state
+                .checked_items()
+                .into_iter()
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>()
+        };
+
+        assert!(state.toggle());
+        assert_eq!(checked(&state), [0]);
+        assert!(state.toggle());
+        assert_eq!(checked(&state), []);
+
+        state.select(1);
+        state.toggle();
+        state.select(2);
+        state.toggle();
+        assert_eq!(checked(&state), [2]);
+        // Radio buttons stay checked until another one is.
+        state.toggle();
+        assert_eq!(checked(&state), [2]);
+
+        state.select(3);
+        assert!(!state.toggle());
+        assert_eq!(checked(&state), [2]);
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 12, 4));
+        MenuWidget::default().render(buf.area, &mut buf, &mut state);
+        let row = |y| (0..12).map(|x| buf[(x, y)].symbol()).collect::<String>();
+        assert_eq!(
+            [row(0), row(1), row(2), row(3)],
+            [
+                " [ ] verbose",
+                "  ( ) small ",
+                "  (x) large ",
+                "    done    ",
+            ]
+        );
+    }
+}